@@ -1,7 +1,5 @@
 use rs_6502::cpu::Cpu;
 
-use simple_logger;
-
 fn main() {
     simple_logger::init().unwrap();
 
@@ -9,10 +7,10 @@ fn main() {
 
     let mut cpu = Cpu::new();
 
-    cpu.load_executable(program).expect("Could not load executable");
+    cpu.load_executable(program, 0x600).expect("Could not load executable");
     cpu.init_registers();
 
-    for _ in 0..program.len() {
-        cpu.step();
-    }
+    let reason = cpu.run_instructions(program.len()).expect("Could not execute instruction");
+
+    log::info!("Stopped: {reason:?}");
 }
@@ -0,0 +1,145 @@
+use proc_macro::{Delimiter, Literal, TokenStream, TokenTree};
+
+use rs_6502::asm::assemble;
+
+/// Assembles 6502 source at compile time into an `rs_6502::asm::StaticAssembly`
+/// holding the bytes and the labels.
+///
+/// The source is either written as tokens or given as a single string
+/// literal. Tokens keep their lines and spacing, comments have to use `//`.
+/// Hex numbers that Rust would read as a float exponent, like `$1E`, need the
+/// string form.
+///
+/// ```ignore
+/// let program = asm6502! {
+///             .org $0200
+///     start:  LDX #0
+///     loop:   INX
+///             BNE loop
+/// };
+///
+/// cpu.load_executable(program.bytes, program.origin)?;
+/// ```
+#[proc_macro]
+pub fn asm6502(input: TokenStream) -> TokenStream {
+    let source = match string_literal(&input) {
+        Some(source) => source,
+        None => {
+            let mut source = String::new();
+            let mut position = None;
+
+            write_tokens(input, &mut source, &mut position);
+            source
+        }
+    };
+
+    let expansion = match assemble(&source) {
+        Ok(assembly) => {
+            let bytes: Vec<String> =
+                assembly.bytes.iter().map(|byte| format!("{byte}u8")).collect();
+
+            let mut labels: Vec<(String, u16)> = assembly.labels.into_iter().collect();
+            labels.sort();
+
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(name, address)| format!("({name:?}, {address}u16)"))
+                .collect();
+
+            format!(
+                "::rs_6502::asm::StaticAssembly {{ origin: {}u16, bytes: &[{}], labels: &[{}] }}",
+                assembly.origin,
+                bytes.join(", "),
+                labels.join(", ")
+            )
+        }
+        Err(error) => format!("compile_error!({:?})", error.to_string()),
+    };
+
+    expansion.parse().expect("Expansion should be valid Rust")
+}
+
+// Writes the tokens back as text. Line breaks and the spacing between tokens
+// come from their spans, as the assembler is line based.
+fn write_tokens(input: TokenStream, source: &mut String, position: &mut Option<(usize, usize)>) {
+    for token in input {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+
+                write_token(open, group.span_open(), source, position);
+                write_tokens(group.stream(), source, position);
+                write_token(close, group.span_close(), source, position);
+            }
+            token => write_token(&token.to_string(), token.span(), source, position),
+        }
+    }
+}
+
+fn write_token(
+    text: &str,
+    span: proc_macro::Span,
+    source: &mut String,
+    position: &mut Option<(usize, usize)>,
+) {
+    let (line, column) = (span.start().line(), span.start().column());
+
+    match *position {
+        Some((last_line, _)) if line > last_line => source.push('\n'),
+        Some((_, last_column)) if column > last_column => source.push(' '),
+        _ => {}
+    }
+
+    source.push_str(text);
+    *position = Some((span.end().line(), span.end().column()));
+}
+
+// The contents of the input if it is a single string literal
+fn string_literal(input: &TokenStream) -> Option<String> {
+    let mut tokens = input.clone().into_iter();
+
+    let (Some(TokenTree::Literal(literal)), None) = (tokens.next(), tokens.next()) else {
+        return None;
+    };
+
+    unescape(&literal)
+}
+
+fn unescape(literal: &Literal) -> Option<String> {
+    let text = literal.to_string();
+
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = &raw[hashes..raw.len() - hashes];
+
+        return Some(inner.strip_prefix('"')?.strip_suffix('"')?.to_string());
+    }
+
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut source = String::new();
+    let mut chars = inner.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            source.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => source.push('\n'),
+            'r' => source.push('\r'),
+            't' => source.push('\t'),
+            '0' => source.push('\0'),
+            // A line continuation skips the leading whitespace of the next line
+            '\n' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            c => source.push(c),
+        }
+    }
+
+    Some(source)
+}
@@ -0,0 +1,50 @@
+use rs_6502_macros::asm6502;
+
+#[test]
+fn test_token_source() {
+    let program = asm6502! {
+        COUNT = 3
+
+                .org $C000
+        start:  LDX #COUNT
+        @loop:  DEX             // Local to start
+                BNE @loop
+                STA ($20),Y
+                .byte "AB", <start
+    };
+
+    assert_eq!(program.origin, 0xC000);
+    assert_eq!(
+        program.bytes,
+        [0xA2, 0x03, 0xCA, 0xD0, 0xFD, 0x91, 0x20, 0x41, 0x42, 0x00]
+    );
+    assert_eq!(program.label("start@loop"), Some(0xC002));
+    assert_eq!(program.label("missing"), None);
+}
+
+#[test]
+fn test_backward_labels() {
+    let program = asm6502! {
+                .org $0200
+        loop:   INX
+                STX $0300
+        jump:   JMP loop
+    };
+
+    assert_eq!(program.bytes, [0xE8, 0x8E, 0x00, 0x03, 0x4C, 0x00, 0x02]);
+    assert_eq!(program.label("loop"), Some(0x0200));
+    assert_eq!(program.label("jump"), Some(0x0204));
+}
+
+#[test]
+fn test_string_source() {
+    let program = asm6502!(
+        r#"
+        .org $1E00
+        JMP $1E00   ; Hex the Rust lexer would reject
+        "#
+    );
+
+    assert_eq!(program.bytes, [0x4C, 0x00, 0x1E]);
+    assert_eq!(program.labels, []);
+}
@@ -0,0 +1,18 @@
+# Downloads the files of Klaus Dormann's tests that tests/klaus_dormann.rs
+# reads into tests/roms. The decimal test is only published as source, it has
+# to be assembled with AS65 afterwards, see tests/roms/readme.md.
+import os
+import urllib.request
+
+REPOSITORY = "https://raw.githubusercontent.com/Klaus2m5/6502_65C02_functional_tests/master"
+FILES = ["bin_files/6502_functional_test.bin", "6502_decimal_test.a65"]
+
+directory = os.path.join(os.path.dirname(__file__), "..", "tests", "roms")
+
+for file in FILES:
+    target = os.path.join(directory, os.path.basename(file))
+
+    with urllib.request.urlopen(f"{REPOSITORY}/{file}") as response, open(target, "wb") as f:
+        f.write(response.read())
+
+    print(f"Wrote {os.path.normpath(target)}")
@@ -5,36 +5,54 @@ with open("data/instructions.json", "r") as f:
 
 invalid_opcodes = instructions["invalid_opcodes"]
 instruction_list = instructions["instructions"]
+undocumented_instructions = instructions["undocumented_instructions"]
+cmos_instructions = instructions["cmos_instructions"]
 addressing_modes = instructions["addressing_modes"]
 
 def valid(opcode: int, instruction: str):
-    mnemonic, addressing_mode = instruction.split(" ")
+    # Cycles marked with * take an extra cycle when a page boundary is crossed
+    mnemonic, addressing_mode, cycles = instruction.split(" ")
+    extra_cycle = cycles.endswith("*")
+
     return "0x{}, InstructionType::{}, AddressingMode::{}, {}, {}, Cpu::{};\n".format(
         f"{opcode:0>2X}",
         mnemonic,
         addressing_modes[addressing_mode],
-        0,
-        "false",
+        cycles.rstrip("*"),
+        "true" if extra_cycle else "false",
         mnemonic.lower()
     )
 
 def invalid(opcode):
-    return valid(opcode, "NOP impl")
+    return valid(opcode, "NOP impl 2")
 
-def generate_instruction_table():
+def generate_instruction_table(overrides={}):
+    # Overrides replace entries of the documented NMOS instruction set
     current_valid_index = 0
     for i in range(0x100):
-        if f"{i:0>2X}" in invalid_opcodes:
+        opcode = f"{i:0>2X}"
+
+        if opcode in overrides:
+            yield valid(i, overrides[opcode])
+        elif opcode in invalid_opcodes:
             yield invalid(i)
         else:
             yield valid(i, instruction_list[current_valid_index])
+
+        if opcode not in invalid_opcodes:
             current_valid_index += 1
 
 def generate_member_fns():
     mnemonics = set()
 
-    for instruction in instruction_list:
-        mnemonic, _ = instruction.split(" ")
+    all_instructions = (
+        instruction_list
+        + list(undocumented_instructions.values())
+        + list(cmos_instructions.values())
+    )
+
+    for instruction in all_instructions:
+        mnemonic = instruction.split(" ")[0]
 
         if mnemonic not in mnemonics:
             yield f"pub fn {mnemonic.lower()}(&mut self) {{\n\ttodo!()\n}}\n\n"
@@ -46,12 +64,19 @@ def main():
         for instruction_fn in generate_member_fns():
             f.write(instruction_fn)
 
-    with open("instruction_table.txt", "w") as f:
-        for instruction in generate_instruction_table():
-            # ugly ass hack
-            if instruction.startswith("0xFF"):
-                instruction = instruction[:-2]
-            f.write(instruction)
+    tables = {
+        "instruction_table.txt": {},
+        "undocumented_instruction_table.txt": undocumented_instructions,
+        "cmos_instruction_table.txt": cmos_instructions,
+    }
+
+    for name, overrides in tables.items():
+        with open(name, "w") as f:
+            for instruction in generate_instruction_table(overrides):
+                # ugly ass hack
+                if instruction.startswith("0xFF"):
+                    instruction = instruction[:-2]
+                f.write(instruction)
 
 if __name__ == "__main__":
     main()
\ No newline at end of file
@@ -0,0 +1,34 @@
+# Copies the first vectors of every opcode from a checkout of
+# https://github.com/SingleStepTests/65x02 into tests/single_step/subset, so
+# they run without the full set. The vectors are written unchanged.
+import json
+import os
+import sys
+
+VECTORS_PER_OPCODE = 5
+CHIPS = {"6502": "6502", "wdc65c02": "65c02"}  # Checkout directory to subset directory
+
+if len(sys.argv) != 2:
+    sys.exit(f"Usage: {sys.argv[0]} <checkout of SingleStepTests/65x02>")
+
+checkout = sys.argv[1]
+subset = os.path.join(os.path.dirname(__file__), "..", "tests", "single_step", "subset")
+
+for chip, name in CHIPS.items():
+    os.makedirs(os.path.join(subset, name), exist_ok=True)
+
+    for opcode in range(0x100):
+        file = f"{opcode:02x}.json"
+        source = os.path.join(checkout, chip, "v1", file)
+
+        if not os.path.exists(source):
+            continue
+
+        with open(source, "r") as f:
+            vectors = json.load(f)[:VECTORS_PER_OPCODE]
+
+        with open(os.path.join(subset, name, file), "w") as f:
+            json.dump(vectors, f, indent=1)
+            f.write("\n")
+
+    print(f"Wrote {chip} vectors to {os.path.normpath(os.path.join(subset, name))}")
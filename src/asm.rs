@@ -0,0 +1,880 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::cpu::Variant;
+use crate::default_memory::DefaultMemory;
+use crate::instruction::AddressingMode;
+use crate::instruction_table::InstructionTable;
+
+/// The output of the assembler. `bytes` are located at `origin`, gaps between
+/// `.org` sections are filled with zeros.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Assembly {
+    pub origin: u16,
+    pub bytes: Vec<u8>,
+    pub labels: HashMap<String, u16>, // Local labels are named `global@local`
+}
+
+impl Assembly {
+    pub fn label(&self, name: &str) -> Option<u16> {
+        self.labels.get(name).copied()
+    }
+}
+
+/// Machine code assembled at compile time by the `asm6502!` macro of the
+/// `rs_6502_macros` crate.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StaticAssembly {
+    pub origin: u16,
+    pub bytes: &'static [u8],
+    pub labels: &'static [(&'static str, u16)],
+}
+
+impl StaticAssembly {
+    pub fn label(&self, name: &str) -> Option<u16> {
+        self.labels
+            .iter()
+            .find(|(label, _)| *label == name)
+            .map(|&(_, address)| address)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(String),
+    Address(String),
+    IndexedX(String),
+    IndexedY(String),
+    Indirect(String),
+    IndirectX(String),
+    IndirectY(String),
+    BitBranch(String, String), // Zero page address and branch target of BBR and BBS
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Empty,
+    Constant(String, String),
+    Org(String),
+    Byte(Vec<String>),
+    Word(Vec<String>),
+    Text(Vec<u8>),
+    Instruction(String, Operand),
+}
+
+struct Line {
+    number: usize,
+    label: Option<String>,
+    statement: Statement,
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '@'
+}
+
+// Removes the comment, leaving semicolons within quotes alone
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ';') => return &line[..i],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+// Splits at commas outside of quotes and parentheses
+fn split_arguments(arguments: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for c in arguments.chars() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+    }
+
+    parts.push(current.trim().to_string());
+    parts
+}
+
+fn parse_string(text: &str) -> Result<Vec<u8>> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .ok_or_else(|| anyhow!("Expected a string, found {text}"))?;
+
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(c) => c,
+                None => return Err(anyhow!("Unterminated escape in {text}")),
+            },
+            c => c,
+        };
+
+        if !c.is_ascii() {
+            return Err(anyhow!("Only ASCII characters are supported, found '{c}'"));
+        }
+
+        bytes.push(c as u8);
+    }
+
+    Ok(bytes)
+}
+
+// Whether the parenthesis at the start is closed by the one at the end
+fn is_enclosed(text: &str) -> bool {
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+
+        if depth == 0 {
+            return i == text.len() - 1 && c == ')';
+        }
+    }
+
+    false
+}
+
+// The operand syntax decides the addressing mode family, the value decides
+// between zero page and absolute forms later on
+fn parse_operand(operand: &str) -> Operand {
+    let operand: String = match operand.contains('\'') {
+        true => operand.trim().to_string(),
+        false => operand.chars().filter(|c| !c.is_whitespace()).collect(),
+    };
+    let upper = operand.to_ascii_uppercase();
+
+    if operand.is_empty() {
+        return Operand::None;
+    }
+
+    if upper == "A" {
+        return Operand::Accumulator;
+    }
+
+    if let Some(value) = operand.strip_prefix('#') {
+        return Operand::Immediate(value.trim().to_string());
+    }
+
+    if let Some(inner) = operand.strip_prefix('(') {
+        if upper.ends_with(",X)") {
+            return Operand::IndirectX(inner[..inner.len() - 3].to_string());
+        }
+
+        if upper.ends_with("),Y") && is_enclosed(&operand[..operand.len() - 2]) {
+            return Operand::IndirectY(inner[..inner.len() - 3].to_string());
+        }
+
+        // `(a) + 1` is an expression rather than an indirect operand
+        if is_enclosed(&operand) {
+            return Operand::Indirect(inner[..inner.len() - 1].to_string());
+        }
+    }
+
+    let arguments = split_arguments(&operand);
+
+    match arguments.as_slice() {
+        [value, index] if index.eq_ignore_ascii_case("X") => Operand::IndexedX(value.clone()),
+        [value, index] if index.eq_ignore_ascii_case("Y") => Operand::IndexedY(value.clone()),
+        [address, target] => Operand::BitBranch(address.clone(), target.clone()),
+        _ => Operand::Address(operand),
+    }
+}
+
+fn parse_line(number: usize, source: &str) -> Result<Line> {
+    let mut rest = strip_comment(source).trim();
+    let mut label = None;
+
+    // Labels end with a colon, constants are assigned with `=`
+    let name_length = rest.find(|c: char| !is_symbol_char(c)).unwrap_or(rest.len());
+    let (name, after) = rest.split_at(name_length);
+
+    if !name.is_empty() {
+        let after = after.trim_start();
+
+        if let Some(value) = after.strip_prefix('=') {
+            return Ok(Line {
+                number,
+                label: None,
+                statement: Statement::Constant(name.to_string(), value.trim().to_string()),
+            });
+        }
+
+        if let Some(after) = after.strip_prefix(':') {
+            label = Some(name.to_string());
+            rest = after.trim();
+        }
+    }
+
+    let (keyword, arguments) = match rest.find(char::is_whitespace) {
+        Some(i) => (&rest[..i], rest[i..].trim()),
+        None => (rest, ""),
+    };
+
+    let statement = match keyword.to_ascii_lowercase().as_str() {
+        "" => Statement::Empty,
+        ".org" => Statement::Org(arguments.to_string()),
+        ".byte" | ".db" => Statement::Byte(split_arguments(arguments)),
+        ".word" | ".dw" => Statement::Word(split_arguments(arguments)),
+        ".text" => {
+            let mut bytes = Vec::new();
+
+            for argument in split_arguments(arguments) {
+                bytes.extend(parse_string(&argument)?);
+            }
+
+            Statement::Text(bytes)
+        }
+        directive if directive.starts_with('.') => {
+            return Err(anyhow!("Unknown directive {directive}"));
+        }
+        _ => Statement::Instruction(keyword.to_ascii_uppercase(), parse_operand(arguments)),
+    };
+
+    Ok(Line {
+        number,
+        label,
+        statement,
+    })
+}
+
+struct Symbols {
+    values: HashMap<String, i64>,
+    scope: String,
+    final_pass: bool,
+}
+
+impl Symbols {
+    // Local labels start with `@` and belong to the last global label
+    fn qualify(&self, name: &str) -> String {
+        match name.starts_with('@') {
+            true => format!("{}{name}", self.scope),
+            false => name.to_string(),
+        }
+    }
+
+    fn define(&mut self, name: &str, value: i64) -> Result<()> {
+        let name = self.qualify(name);
+
+        if !self.final_pass && self.values.contains_key(&name) {
+            return Err(anyhow!("Symbol {name} is defined twice"));
+        }
+
+        self.values.insert(name, value);
+
+        Ok(())
+    }
+
+    fn evaluate(&self, source: &str, pc: u16) -> Result<Option<i64>> {
+        let mut parser = ExpressionParser {
+            source: source.as_bytes(),
+            position: 0,
+            symbols: self,
+            pc,
+        };
+
+        let value = parser.expression(0)?;
+        parser.skip_whitespace();
+
+        if parser.position < source.len() {
+            return Err(anyhow!(
+                "Unexpected '{}' in {source}",
+                &source[parser.position..]
+            ));
+        }
+
+        match value {
+            None if self.final_pass => Err(anyhow!("Undefined symbol in {source}")),
+            value => Ok(value),
+        }
+    }
+}
+
+// Binary operators of the precedence levels, loosest first
+const PRECEDENCE: [&[&str]; 6] = [
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+// Values are `None` while they depend on symbols that are not defined yet
+struct ExpressionParser<'a> {
+    source: &'a [u8],
+    position: usize,
+    symbols: &'a Symbols,
+    pc: u16,
+}
+
+impl ExpressionParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.source.get(self.position).is_some_and(u8::is_ascii_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn eat(&mut self, operator: &str) -> bool {
+        self.skip_whitespace();
+
+        let matches = self.source[self.position..].starts_with(operator.as_bytes());
+
+        if matches {
+            self.position += operator.len();
+        }
+
+        matches
+    }
+
+    fn expression(&mut self, level: usize) -> Result<Option<i64>> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+
+        let mut value = self.expression(level + 1)?;
+
+        while let Some(operator) = operators.iter().find(|operator| self.eat(operator)) {
+            let right = self.expression(level + 1)?;
+
+            value = match (value, right) {
+                (Some(left), Some(right)) => Some(match *operator {
+                    "|" => left | right,
+                    "^" => left ^ right,
+                    "&" => left & right,
+                    "<<" => left << (right & 0x3F),
+                    ">>" => left >> (right & 0x3F),
+                    "+" => left.wrapping_add(right),
+                    "-" => left.wrapping_sub(right),
+                    "*" => left.wrapping_mul(right),
+                    _ if right == 0 => return Err(anyhow!("Division by zero")),
+                    "/" => left / right,
+                    _ => left % right,
+                }),
+                _ => None,
+            };
+        }
+
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<Option<i64>> {
+        for operator in ["-", "~", "<", ">"] {
+            if self.eat(operator) {
+                let value = self.unary()?;
+
+                return Ok(value.map(|value| match operator {
+                    "-" => value.wrapping_neg(),
+                    "~" => !value,
+                    "<" => value & 0xFF,
+                    _ => (value >> 8) & 0xFF,
+                }));
+            }
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Option<i64>> {
+        self.skip_whitespace();
+
+        let start = self.position;
+        let Some(&c) = self.source.get(start) else {
+            return Err(anyhow!("Expected a value"));
+        };
+
+        if self.eat("(") {
+            let value = self.expression(0)?;
+
+            return match self.eat(")") {
+                true => Ok(value),
+                false => Err(anyhow!("Expected ')'")),
+            };
+        }
+
+        if self.eat("*") {
+            return Ok(Some(self.pc as i64));
+        }
+
+        if c == b'\'' {
+            return match self.source.get(start + 1..start + 3) {
+                Some(&[c, b'\'']) => {
+                    self.position += 3;
+                    Ok(Some(c as i64))
+                }
+                _ => Err(anyhow!("Invalid character literal")),
+            };
+        }
+
+        let (radix, digits_start) = match c {
+            b'$' => (16, start + 1),
+            b'%' => (2, start + 1),
+            b'0'..=b'9' => (10, start),
+            _ => (0, start),
+        };
+
+        let end = self.source[digits_start..]
+            .iter()
+            .position(|&c| !is_symbol_char(c as char))
+            .map_or(self.source.len(), |length| digits_start + length);
+
+        self.position = end;
+
+        let text = std::str::from_utf8(&self.source[digits_start..end])?;
+
+        if text.is_empty() {
+            return Err(anyhow!("Expected a value at '{}'", c as char));
+        }
+
+        if radix != 0 {
+            let value = i64::from_str_radix(text, radix).map_err(|_| {
+                anyhow!(
+                    "Invalid number {}",
+                    std::str::from_utf8(&self.source[start..end]).unwrap_or(text)
+                )
+            })?;
+
+            return Ok(Some(value));
+        }
+
+        Ok(self.symbols.values.get(&self.symbols.qualify(text)).copied())
+    }
+}
+
+fn byte(value: i64) -> Result<u8> {
+    match value {
+        -0x80..=0xFF => Ok(value as u8),
+        _ => Err(anyhow!("Value {value} does not fit into a byte")),
+    }
+}
+
+fn word(value: i64) -> Result<u16> {
+    match value {
+        -0x8000..=0xFFFF => Ok(value as u16),
+        _ => Err(anyhow!("Value {value} does not fit into a word")),
+    }
+}
+
+/// Assembles standard 6502 syntax into machine code in two passes.
+///
+/// Lines hold an optional `label:`, followed by an instruction or one of the
+/// directives `.org`, `.byte`, `.word` and `.text`. Constants are defined with
+/// `NAME = expression`. Labels starting with `@` are local to the previous
+/// global label. Numbers are decimal, `$` hex, `%` binary or `'c'` characters,
+/// `*` is the current address and `<` and `>` take the low and high byte.
+/// Zero page forms are picked when the operand is known to fit in the first
+/// pass.
+pub struct Assembler {
+    variant: Variant,
+    undocumented_opcodes: bool,
+}
+
+impl Default for Assembler {
+    fn default() -> Assembler {
+        Assembler::new(Variant::Nmos6502)
+    }
+}
+
+impl Assembler {
+    pub fn new(variant: Variant) -> Assembler {
+        Assembler {
+            variant,
+            undocumented_opcodes: false,
+        }
+    }
+
+    /// Accepts the undocumented NMOS instructions, like `LAX` or `DCP`.
+    pub fn set_undocumented_opcodes(&mut self, enabled: bool) {
+        self.undocumented_opcodes = enabled;
+    }
+
+    // Opcodes by mnemonic and addressing mode. The first opcode of a
+    // combination wins, except for the placeholder NOPs of the tables.
+    fn opcodes(&self) -> HashMap<(String, AddressingMode), u8> {
+        let mut opcodes = HashMap::new();
+        let table =
+            InstructionTable::<DefaultMemory>::for_variant(self.variant, self.undocumented_opcodes);
+
+        for instruction in table {
+            let key = (
+                instruction.instruction_type.mnemonic(instruction.opcode),
+                instruction.mode,
+            );
+
+            match instruction.opcode {
+                0xEA => {
+                    opcodes.insert(key, 0xEA);
+                }
+                opcode => {
+                    opcodes.entry(key).or_insert(opcode);
+                }
+            }
+        }
+
+        opcodes
+    }
+
+    pub fn assemble(&self, source: &str) -> Result<Assembly> {
+        let lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                parse_line(i + 1, line).map_err(|error| anyhow!("Line {}: {error}", i + 1))
+            })
+            .collect::<Result<Vec<Line>>>()?;
+
+        let opcodes = self.opcodes();
+        let mut symbols = Symbols {
+            values: HashMap::new(),
+            scope: String::new(),
+            final_pass: false,
+        };
+
+        // The first pass fixes the addressing modes, the second one emits
+        // code with the same sizes
+        let mut modes = vec![None; lines.len()];
+        let mut labels = HashMap::new();
+        let mut output = Output::default();
+
+        for final_pass in [false, true] {
+            output = Output::default();
+            symbols.final_pass = final_pass;
+            symbols.scope.clear();
+
+            for (line, mode) in lines.iter().zip(modes.iter_mut()) {
+                self.assemble_line(line, mode, &opcodes, &mut symbols, &mut output, &mut labels)
+                    .map_err(|error| anyhow!("Line {}: {error}", line.number))?;
+            }
+        }
+
+        Ok(Assembly {
+            origin: output.origin.unwrap_or(output.pc),
+            bytes: output.bytes,
+            labels,
+        })
+    }
+
+    fn assemble_line(
+        &self,
+        line: &Line,
+        mode: &mut Option<AddressingMode>,
+        opcodes: &HashMap<(String, AddressingMode), u8>,
+        symbols: &mut Symbols,
+        output: &mut Output,
+        labels: &mut HashMap<String, u16>,
+    ) -> Result<()> {
+        if let Some(label) = &line.label {
+            if !label.starts_with('@') {
+                symbols.scope = label.clone();
+            }
+
+            symbols.define(label, output.pc as i64)?;
+            labels.insert(symbols.qualify(label), output.pc);
+        }
+
+        let pc = output.pc;
+
+        match &line.statement {
+            Statement::Empty => {}
+            Statement::Constant(name, value) => {
+                // Constants that depend on later labels are defined in the final pass
+                if let Some(value) = symbols.evaluate(value, pc)? {
+                    symbols.define(name, value)?;
+                }
+            }
+            Statement::Org(address) => {
+                let address = symbols
+                    .evaluate(address, pc)?
+                    .ok_or_else(|| anyhow!(".org needs an address that is already known"))?;
+
+                output.pc = word(address)?;
+            }
+            Statement::Byte(values) => {
+                for value in values {
+                    match value.starts_with('"') {
+                        true => output.emit(&parse_string(value)?)?,
+                        false => {
+                            let value = symbols.evaluate(value, output.pc)?.unwrap_or_default();
+                            output.emit(&[byte(value)?])?;
+                        }
+                    }
+                }
+            }
+            Statement::Word(values) => {
+                for value in values {
+                    let value = symbols.evaluate(value, output.pc)?.unwrap_or_default();
+                    output.emit(&word(value)?.to_le_bytes())?;
+                }
+            }
+            Statement::Text(bytes) => output.emit(bytes)?,
+            Statement::Instruction(mnemonic, operand) => {
+                let bytes = self.encode(mnemonic, operand, mode, opcodes, symbols, pc)?;
+                output.emit(&bytes)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode(
+        &self,
+        mnemonic: &str,
+        operand: &Operand,
+        mode: &mut Option<AddressingMode>,
+        opcodes: &HashMap<(String, AddressingMode), u8>,
+        symbols: &Symbols,
+        pc: u16,
+    ) -> Result<Vec<u8>> {
+        let has_mode = |mode: AddressingMode| opcodes.contains_key(&(mnemonic.to_string(), mode));
+
+        if !opcodes.keys().any(|(name, _)| name == mnemonic) {
+            return Err(anyhow!("Unknown instruction {mnemonic}"));
+        }
+
+        let value = |expression: &str| symbols.evaluate(expression, pc);
+
+        // Zero page forms are only used if the value is known in the first pass
+        let sized =
+            |value: Option<i64>, zero_page: AddressingMode, absolute: AddressingMode| match value {
+                Some(0..=0xFF) if has_mode(zero_page) => zero_page,
+                _ if has_mode(absolute) => absolute,
+                _ => zero_page,
+            };
+
+        let (chosen, value) = match operand {
+            Operand::None if has_mode(AddressingMode::Implied) => (AddressingMode::Implied, None),
+            Operand::None | Operand::Accumulator => (AddressingMode::Accumulator, None),
+            Operand::Immediate(expression) => (AddressingMode::Immediate, value(expression)?),
+            Operand::Address(expression) if has_mode(AddressingMode::Relative) => {
+                (AddressingMode::Relative, value(expression)?)
+            }
+            Operand::Address(expression) => {
+                let value = value(expression)?;
+                (
+                    sized(value, AddressingMode::ZeroPage, AddressingMode::Absolute),
+                    value,
+                )
+            }
+            Operand::IndexedX(expression) => {
+                let value = value(expression)?;
+                (
+                    sized(value, AddressingMode::ZeroPageX, AddressingMode::AbsoluteX),
+                    value,
+                )
+            }
+            Operand::IndexedY(expression) => {
+                let value = value(expression)?;
+                (
+                    sized(value, AddressingMode::ZeroPageY, AddressingMode::AbsoluteY),
+                    value,
+                )
+            }
+            Operand::Indirect(expression) => {
+                let value = value(expression)?;
+                (
+                    sized(
+                        value,
+                        AddressingMode::ZeroPageIndirect,
+                        AddressingMode::Indirect,
+                    ),
+                    value,
+                )
+            }
+            Operand::IndirectX(expression) => {
+                let value = value(expression)?;
+                let absolute = AddressingMode::AbsoluteIndexedIndirect;
+                (sized(value, AddressingMode::IndirectX, absolute), value)
+            }
+            Operand::IndirectY(expression) => (AddressingMode::IndirectY, value(expression)?),
+            Operand::BitBranch(address, _) => (AddressingMode::ZeroPageRelative, value(address)?),
+        };
+
+        let mode = *mode.get_or_insert(chosen);
+        let opcode = *opcodes
+            .get(&(mnemonic.to_string(), mode))
+            .ok_or_else(|| anyhow!("{mnemonic} does not support {mode:?} addressing"))?;
+
+        let value = value.unwrap_or_default();
+        let mut bytes = vec![opcode];
+
+        match mode {
+            AddressingMode::Implied | AddressingMode::Accumulator => {}
+            AddressingMode::Relative => {
+                bytes.push(branch_offset(value, pc.wrapping_add(2), symbols)?)
+            }
+            AddressingMode::ZeroPageRelative => {
+                let Operand::BitBranch(_, target) = operand else {
+                    unreachable!()
+                };
+
+                let target = symbols.evaluate(target, pc)?.unwrap_or_default();
+
+                bytes.push(byte(value)?);
+                bytes.push(branch_offset(target, pc.wrapping_add(3), symbols)?);
+            }
+            mode if mode.operand_size() == 1 => bytes.push(byte(value)?),
+            _ => bytes.extend(word(value)?.to_le_bytes()),
+        }
+
+        Ok(bytes)
+    }
+}
+
+// Offsets are only checked in the final pass, forward targets are unknown before
+fn branch_offset(target: i64, next: u16, symbols: &Symbols) -> Result<u8> {
+    let offset = target - next as i64;
+
+    match (-0x80..=0x7F).contains(&offset) || !symbols.final_pass {
+        true => Ok(offset as u8),
+        false => Err(anyhow!("Branch target ${target:04X} is out of range")),
+    }
+}
+
+#[derive(Default)]
+struct Output {
+    pc: u16,
+    origin: Option<u16>,
+    bytes: Vec<u8>,
+}
+
+impl Output {
+    fn emit(&mut self, bytes: &[u8]) -> Result<()> {
+        let origin = *self.origin.get_or_insert(self.pc);
+
+        if self.pc < origin {
+            return Err(anyhow!(
+                "Code at ${:04X} before the start at ${origin:04X}",
+                self.pc
+            ));
+        }
+
+        let offset = (self.pc - origin) as usize;
+
+        if offset + bytes.len() > 0x10000 - origin as usize {
+            return Err(anyhow!("Code runs past $FFFF"));
+        }
+
+        if self.bytes.len() < offset + bytes.len() {
+            self.bytes.resize(offset + bytes.len(), 0);
+        }
+
+        self.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self.pc = self.pc.wrapping_add(bytes.len() as u16);
+
+        Ok(())
+    }
+}
+
+/// Assembles `source` for the NMOS 6502.
+pub fn assemble(source: &str) -> Result<Assembly> {
+    Assembler::default().assemble(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let assembly = assemble(
+            r#"
+            SCREEN = $0400
+            ZP = $20
+
+                    .org $C000
+            start:  LDX #0
+            @loop:  LDA message,X   ; Local to start
+                    BEQ @done
+                    STA SCREEN,X
+                    STA ZP
+                    INX
+                    BNE @loop
+            @done:  JMP (vector)
+            message:
+                    .text "HI"
+                    .byte 0, <start, >start
+            vector: .word start, * + 2
+            "#,
+        )
+        .unwrap();
+
+        let expected = [
+            0xA2, 0x00, // LDX #0
+            0xBD, 0x12, 0xC0, // LDA message,X
+            0xF0, 0x08, // BEQ @done
+            0x9D, 0x00, 0x04, // STA SCREEN,X
+            0x85, 0x20, // STA ZP
+            0xE8, // INX
+            0xD0, 0xF3, // BNE @loop
+            0x6C, 0x17, 0xC0, // JMP (vector)
+            0x48, 0x49, 0x00, 0x00, 0xC0, // message
+            0x00, 0xC0, 0x1B, 0xC0, // vector
+        ];
+
+        assert_eq!(assembly.origin, 0xC000);
+        assert_eq!(assembly.bytes, expected);
+        assert_eq!(assembly.label("start@loop"), Some(0xC002));
+        assert_eq!(assembly.label("vector"), Some(0xC017));
+
+        // Forward references to the zero page keep the absolute form
+        let assembly = assemble("LDA later\nlater = $10\nLDA later, x\nLDA (later),y").unwrap();
+        assert_eq!(assembly.bytes, [0xAD, 0x10, 0x00, 0xB5, 0x10, 0xB1, 0x10]);
+
+        let cmos = Assembler::new(Variant::Cmos65C02);
+        let assembly = cmos.assemble(".org $10\nloop: BBS3 $12,loop\nORA ($20)\nINC").unwrap();
+        assert_eq!(assembly.bytes, [0xBF, 0x12, 0xFD, 0x12, 0x20, 0x1A]);
+
+        for source in [
+            "FOO",
+            "LDA #",
+            "BNE $1000",
+            "STA #1",
+            ".org x\nx:",
+            "a:\na:",
+        ] {
+            assert!(assemble(source).is_err(), "{source}");
+        }
+    }
+}
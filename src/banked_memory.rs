@@ -0,0 +1,379 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+
+use crate::memory::{Memory, MemoryError, RomWritePolicy};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BankKind {
+    Ram,
+    Rom,
+}
+
+struct Bank {
+    kind: BankKind,
+    data: Vec<u8>,
+}
+
+struct Window {
+    start: u16,
+    size: usize,
+    select_register: u16,
+    banks: Vec<Bank>,
+    selected: usize,
+}
+
+impl Window {
+    fn contains(&self, address: u16) -> bool {
+        (address as usize) >= self.start as usize
+            && (address as usize) < self.start as usize + self.size
+    }
+
+    fn overlaps(&self, start: u16, size: usize) -> bool {
+        (start as usize) < self.start as usize + self.size
+            && (self.start as usize) < start as usize + size
+    }
+}
+
+/// Flat RAM with windows that can be paged to one of several banks. Writing a
+/// value to the select register of a window selects bank `value % banks`.
+pub struct BankedMemory {
+    data: [u8; 1 << 16],
+    windows: Vec<Window>,
+    rom_write_policy: RomWritePolicy,
+    error: Option<MemoryError>,
+}
+
+impl BankedMemory {
+    /// Decides what happens to writes to a selected ROM bank.
+    pub fn set_rom_write_policy(&mut self, policy: RomWritePolicy) {
+        self.rom_write_policy = policy;
+    }
+
+    /// Adds a window of `size` bytes at `start` and returns its index. Until
+    /// banks are added the window shows the underlying flat memory. Windows
+    /// must not overlap.
+    pub fn add_window(&mut self, start: u16, size: usize, select_register: u16) -> Result<usize> {
+        if size == 0 || start as usize + size > 1 << 16 {
+            return Err(anyhow!(
+                "Window of size {size} does not fit at 0x{start:04X}"
+            ));
+        }
+
+        if let Some(index) = self.windows.iter().position(|window| window.overlaps(start, size)) {
+            return Err(anyhow!("Window at 0x{start:04X} overlaps window {index}"));
+        }
+
+        self.windows.push(Window {
+            start,
+            size,
+            select_register,
+            banks: Vec::new(),
+            selected: 0,
+        });
+
+        Ok(self.windows.len() - 1)
+    }
+
+    /// Adds a bank to the window and returns its index. Data shorter than the
+    /// window is padded with zeros.
+    pub fn add_bank(&mut self, window: usize, kind: BankKind, data: &[u8]) -> Result<usize> {
+        let window = self.window_mut(window)?;
+
+        if data.len() > window.size {
+            return Err(anyhow!(
+                "Bank of size {} exceeds window size {}",
+                data.len(),
+                window.size
+            ));
+        }
+
+        let mut bank = vec![0; window.size];
+        bank[..data.len()].copy_from_slice(data);
+
+        window.banks.push(Bank { kind, data: bank });
+
+        Ok(window.banks.len() - 1)
+    }
+
+    /// Splits the file into window sized banks and adds them to the window.
+    /// Returns the indices of the added banks.
+    pub fn load_banks_from_file(
+        &mut self,
+        window: usize,
+        kind: BankKind,
+        name: &str,
+    ) -> Result<Vec<usize>> {
+        let content = fs::read(name)?;
+        let size = self.window_mut(window)?.size;
+
+        content.chunks(size).map(|bank| self.add_bank(window, kind, bank)).collect()
+    }
+
+    pub fn select_bank(&mut self, window: usize, bank: usize) -> Result<()> {
+        let window = self.window_mut(window)?;
+
+        if bank >= window.banks.len() {
+            return Err(anyhow!("Bank {bank} does not exist"));
+        }
+
+        window.selected = bank;
+
+        Ok(())
+    }
+
+    pub fn selected_bank(&self, window: usize) -> Option<usize> {
+        let window = self.windows.get(window)?;
+
+        (!window.banks.is_empty()).then_some(window.selected)
+    }
+
+    pub fn bank(&self, window: usize, bank: usize) -> Option<&[u8]> {
+        let bank = self.windows.get(window)?.banks.get(bank)?;
+
+        Some(&bank.data)
+    }
+
+    pub fn bank_kind(&self, window: usize, bank: usize) -> Option<BankKind> {
+        Some(self.windows.get(window)?.banks.get(bank)?.kind)
+    }
+
+    fn window_mut(&mut self, window: usize) -> Result<&mut Window> {
+        self.windows
+            .get_mut(window)
+            .ok_or_else(|| anyhow!("Window {window} does not exist"))
+    }
+
+    fn mapped_bank(&self, address: u16) -> Option<(&Bank, usize)> {
+        let window = self
+            .windows
+            .iter()
+            .find(|window| window.contains(address) && !window.banks.is_empty())?;
+
+        Some((
+            &window.banks[window.selected],
+            (address - window.start) as usize,
+        ))
+    }
+
+    fn mapped_bank_mut(&mut self, address: u16) -> Option<(&mut Bank, usize)> {
+        let window = self
+            .windows
+            .iter_mut()
+            .find(|window| window.contains(address) && !window.banks.is_empty())?;
+
+        let offset = (address - window.start) as usize;
+
+        Some((&mut window.banks[window.selected], offset))
+    }
+}
+
+impl Memory for BankedMemory {
+    fn new() -> Self {
+        Self {
+            data: [0u8; 1 << 16],
+            windows: Vec::new(),
+            rom_write_policy: RomWritePolicy::Ignore,
+            error: None,
+        }
+    }
+
+    fn read_byte(&self, address: u16) -> u8 {
+        match self.mapped_bank(address) {
+            Some((bank, offset)) => bank.data[offset],
+            None => self.data[address as usize],
+        }
+    }
+
+    fn read_short(&self, address: u16) -> u16 {
+        u16::from_le_bytes([
+            self.read_byte(address),
+            self.read_byte(address.wrapping_add(1)),
+        ])
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        let mut is_select_register = false;
+
+        for window in self.windows.iter_mut() {
+            if window.select_register == address && !window.banks.is_empty() {
+                window.selected = value as usize % window.banks.len();
+                is_select_register = true;
+            }
+        }
+
+        if is_select_register {
+            return;
+        }
+
+        let policy = self.rom_write_policy;
+
+        match self.mapped_bank_mut(address) {
+            Some((bank, offset)) if bank.kind == BankKind::Ram => bank.data[offset] = value,
+            Some((bank, offset)) => match policy.handle(address, value) {
+                Ok(true) => bank.data[offset] = value,
+                Ok(false) => {}
+                Err(error) => self.error = Some(error),
+            },
+            None => self.data[address as usize] = value,
+        }
+    }
+
+    fn write_short(&mut self, address: u16, value: u16) {
+        self.write_byte(address, value as u8);
+        self.write_byte(address.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    fn load_from_file(&mut self, name: &str, address: u16) -> Result<()> {
+        let content = fs::read(name)?;
+
+        self.load(&content, address)
+    }
+
+    /// Loads into whatever is currently mapped at the addresses, including
+    /// selected ROM banks.
+    fn load(&mut self, executable: &[u8], address: u16) -> Result<()> {
+        if address as usize + executable.len() > 1 << 16 {
+            return Err(anyhow!("Executable does not fit at 0x{address:04X}"));
+        }
+
+        for (i, &byte) in executable.iter().enumerate() {
+            let address = address + i as u16;
+
+            match self.mapped_bank_mut(address) {
+                Some((bank, offset)) => bank.data[offset] = byte,
+                None => self.data[address as usize] = byte,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn take_error(&mut self) -> Option<MemoryError> {
+        self.error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bank_switching() {
+        let mut memory = BankedMemory::new();
+
+        let window = memory.add_window(0x8000, 0x2000, 0xFFF0).unwrap();
+
+        memory.write_byte(0x8000, 0x01);
+        assert_eq!(memory.read_byte(0x8000), 0x01);
+
+        memory.add_bank(window, BankKind::Rom, &[0xA0, 0xA1]).unwrap();
+        memory.add_bank(window, BankKind::Ram, &[]).unwrap();
+        memory.add_bank(window, BankKind::Rom, &[0xC0]).unwrap();
+
+        assert_eq!(memory.selected_bank(window), Some(0));
+        assert_eq!(memory.read_short(0x8000), 0xA1A0);
+
+        memory.write_byte(0x8000, 0x55);
+        assert_eq!(memory.read_byte(0x8000), 0xA0);
+
+        memory.write_byte(0xFFF0, 1);
+        memory.write_byte(0x9FFF, 0x55);
+        assert_eq!(memory.read_byte(0x9FFF), 0x55);
+
+        // Latch values wrap around the number of banks
+        memory.write_byte(0xFFF0, 5);
+        assert_eq!(memory.selected_bank(window), Some(2));
+        assert_eq!(memory.read_byte(0x8000), 0xC0);
+
+        memory.select_bank(window, 1).unwrap();
+        assert_eq!(memory.read_byte(0x9FFF), 0x55);
+        assert_eq!(memory.bank(window, 1).unwrap()[0x1FFF], 0x55);
+        assert!(memory.select_bank(window, 3).is_err());
+
+        assert_eq!(memory.read_byte(0xA000), 0x00);
+    }
+
+    #[test]
+    fn test_overlapping_windows() {
+        let mut memory = BankedMemory::new();
+
+        memory.add_window(0x8000, 0x2000, 0xFFF0).unwrap();
+
+        assert!(memory.add_window(0x9FFF, 0x0100, 0xFFF1).is_err());
+        assert!(memory.add_window(0x7000, 0x1001, 0xFFF1).is_err());
+        assert!(memory.add_window(0x7000, 0x3000, 0xFFF1).is_err());
+        assert_eq!(memory.add_window(0xA000, 0x2000, 0xFFF1).unwrap(), 1);
+        assert_eq!(memory.add_window(0x7000, 0x1000, 0xFFF2).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_load_banks_from_file() {
+        let path = std::env::temp_dir().join(format!("rs_6502_banks_{}", std::process::id()));
+        fs::write(&path, [0x11; 0x2000 + 0x10]).unwrap();
+
+        let mut memory = BankedMemory::new();
+        let window = memory.add_window(0xC000, 0x1000, 0xFFF0).unwrap();
+        let banks = memory.load_banks_from_file(window, BankKind::Rom, path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        // The last bank is padded to the window size
+        assert_eq!(banks.unwrap(), [0, 1, 2]);
+        assert_eq!(
+            memory.bank(window, 2).unwrap()[..0x11],
+            [[0x11; 0x10].as_slice(), &[0]].concat()
+        );
+        assert_eq!(memory.bank_kind(window, 2), Some(BankKind::Rom));
+        assert_eq!(memory.bank_kind(window, 3), None);
+        assert!(memory.load_banks_from_file(window, BankKind::Rom, "missing.bin").is_err());
+        assert!(memory.load_banks_from_file(1, BankKind::Rom, "missing.bin").is_err());
+    }
+
+    #[test]
+    fn test_rom_protection_across_switches() {
+        let mut memory = BankedMemory::new();
+
+        let window = memory.add_window(0x8000, 0x0100, 0xFFF0).unwrap();
+        memory.add_bank(window, BankKind::Ram, &[]).unwrap();
+        memory.add_bank(window, BankKind::Rom, &[0xEE]).unwrap();
+
+        assert_eq!(memory.bank_kind(window, 0), Some(BankKind::Ram));
+        memory.write_byte(0x8000, 0x12);
+
+        // The ROM bank keeps its content while it is selected and afterwards
+        memory.write_byte(0xFFF0, 1);
+        memory.write_byte(0x8000, 0x34);
+        assert_eq!(memory.read_byte(0x8000), 0xEE);
+
+        memory.write_byte(0xFFF0, 0);
+        assert_eq!(memory.read_byte(0x8000), 0x12);
+
+        memory.write_byte(0xFFF0, 1);
+        assert_eq!(memory.read_byte(0x8000), 0xEE);
+        assert_eq!(memory.bank(window, 1).unwrap()[0], 0xEE);
+        assert_eq!(memory.take_error(), None);
+
+        memory.set_rom_write_policy(RomWritePolicy::Error);
+        memory.write_byte(0x8000, 0x56);
+        assert_eq!(memory.read_byte(0x8000), 0xEE);
+        assert_eq!(
+            memory.take_error(),
+            Some(MemoryError::RomWrite {
+                address: 0x8000,
+                value: 0x56
+            })
+        );
+        assert_eq!(memory.take_error(), None);
+
+        // Switching banks is not affected by the policy
+        memory.write_byte(0xFFF0, 0);
+        memory.write_byte(0x8000, 0x78);
+        assert_eq!(memory.take_error(), None);
+
+        memory.write_byte(0xFFF0, 1);
+        memory.set_rom_write_policy(RomWritePolicy::Allow);
+        memory.write_byte(0x8000, 0x9A);
+        assert_eq!(memory.bank(window, 1).unwrap()[0], 0x9A);
+        assert_eq!(memory.take_error(), None);
+    }
+}
@@ -0,0 +1,80 @@
+use std::{env, fs, process};
+
+use anyhow::{anyhow, Result};
+
+use rs_6502::cpu::StopReason;
+use rs_6502::sim65::{self, ERROR_EXIT_CODE, TIMEOUT_EXIT_CODE};
+
+const USAGE: &str = "Usage: sim65 [-c] [-x max-cycles] program [arguments]";
+
+struct Options {
+    print_cycles: bool,
+    max_cycles: Option<u32>,
+    args: Vec<String>, // The program followed by its arguments
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options> {
+    let mut options = Options {
+        print_cycles: false,
+        max_cycles: None,
+        args: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" => options.print_cycles = true,
+            "-x" => {
+                let cycles = args.next().ok_or_else(|| anyhow!("-x needs a cycle count"))?;
+                options.max_cycles = Some(cycles.parse()?);
+            }
+            _ if arg.starts_with('-') => return Err(anyhow!("Unknown option {arg}")),
+            _ => {
+                options.args.push(arg);
+                options.args.extend(args);
+                break;
+            }
+        }
+    }
+
+    if options.args.is_empty() {
+        return Err(anyhow!("No program given"));
+    }
+
+    Ok(options)
+}
+
+// Runs the program and returns the exit code for the host
+fn run(options: Options) -> Result<i32> {
+    let binary = fs::read(&options.args[0])?;
+    let mut cpu = sim65::load(&binary, options.args)?;
+
+    let reason = match options.max_cycles {
+        Some(cycles) => cpu.run_cycles(cycles)?,
+        None => cpu.run_until(|_| false)?,
+    };
+
+    if options.print_cycles {
+        eprintln!("{} cycles", cpu.cycles);
+    }
+
+    match reason {
+        StopReason::Exited { code } => Ok(code),
+        StopReason::BudgetExhausted => {
+            eprintln!("Maximum number of cycles reached");
+            Ok(TIMEOUT_EXIT_CODE)
+        }
+        reason => Err(anyhow!("Program stopped: {reason:?}")),
+    }
+}
+
+fn main() {
+    let code = parse_options(env::args().skip(1))
+        .map_err(|error| anyhow!("{error}\n{USAGE}"))
+        .and_then(run)
+        .unwrap_or_else(|error| {
+            eprintln!("{error}");
+            ERROR_EXIT_CODE
+        });
+
+    process::exit(code);
+}
@@ -0,0 +1,472 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+
+use crate::memory::{Memory, MemoryError, RomWritePolicy};
+
+/// A memory mapped device. Offsets are relative to the start of the range the
+/// device is mapped to. Failed accesses are reported by `Bus::take_error`, a
+/// failed read leaves the data bus as it was.
+pub trait Device {
+    fn read(&mut self, offset: u16) -> Result<u8>;
+    fn write(&mut self, offset: u16, value: u8) -> Result<()>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnmappedReadPolicy {
+    OpenBus, // Returns the last value seen on the data bus
+    Zero,
+    Error,
+}
+
+enum Target {
+    Ram(Vec<u8>),
+    Rom(Vec<u8>),
+    Device(RefCell<Box<dyn Device>>),
+    Mirror(RangeInclusive<u16>),
+}
+
+struct Region {
+    range: RangeInclusive<u16>,
+    target: Target,
+}
+
+/// Address space made up of RAM, ROM and device regions. Regions mapped later
+/// take precedence over earlier ones where they overlap.
+pub struct Bus {
+    regions: Vec<Region>,
+    unmapped_read_policy: UnmappedReadPolicy,
+    rom_write_policy: RomWritePolicy,
+    data_bus: Cell<u8>,
+    error: Cell<Option<MemoryError>>,
+}
+
+fn range_len(range: &RangeInclusive<u16>) -> usize {
+    (*range.end() as usize + 1).saturating_sub(*range.start() as usize)
+}
+
+impl Bus {
+    pub fn map(&mut self, range: RangeInclusive<u16>, device: Box<dyn Device>) {
+        self.add_region(range, Target::Device(RefCell::new(device)));
+    }
+
+    pub fn map_ram(&mut self, range: RangeInclusive<u16>) {
+        let size = range_len(&range);
+
+        self.add_region(range, Target::Ram(vec![0; size]));
+    }
+
+    /// Maps a ROM image into the range. Images smaller than the range are
+    /// mirrored across it.
+    pub fn map_rom(&mut self, range: RangeInclusive<u16>, data: Vec<u8>) {
+        assert!(!data.is_empty(), "ROM image must not be empty");
+
+        self.add_region(range, Target::Rom(data));
+    }
+
+    /// Makes accesses to `range` go to `target`, repeating `target` if it is
+    /// smaller than `range`. Mirrors may point into other mirrors, addresses
+    /// whose mirrors lead back to themselves count as unmapped.
+    pub fn mirror(&mut self, range: RangeInclusive<u16>, target: RangeInclusive<u16>) {
+        assert!(target.start() <= target.end(), "Invalid range {target:?}");
+
+        self.add_region(range, Target::Mirror(target));
+    }
+
+    pub fn set_unmapped_read_policy(&mut self, policy: UnmappedReadPolicy) {
+        self.unmapped_read_policy = policy;
+    }
+
+    pub fn set_rom_write_policy(&mut self, policy: RomWritePolicy) {
+        self.rom_write_policy = policy;
+    }
+
+    fn add_region(&mut self, range: RangeInclusive<u16>, target: Target) {
+        assert!(range.start() <= range.end(), "Invalid range {range:?}");
+
+        self.regions.push(Region { range, target });
+    }
+
+    /// Returns the index of the region backing `address` and the offset into it.
+    fn resolve(&self, address: u16) -> Option<(usize, usize)> {
+        let mut address = address;
+
+        // Every hop goes through a different mirror unless the mirrors form a
+        // cycle, so more hops than regions means there is no backing region
+        for _ in 0..=self.regions.len() {
+            let index = self.regions.iter().rposition(|region| region.range.contains(&address))?;
+            let region = &self.regions[index];
+            let offset = (address - region.range.start()) as usize;
+
+            match &region.target {
+                Target::Mirror(target) => {
+                    address = (*target.start() as usize + offset % range_len(target)) as u16;
+                }
+                _ => return Some((index, offset)),
+            }
+        }
+
+        None
+    }
+
+    fn read_unmapped(&self, address: u16) -> u8 {
+        match self.unmapped_read_policy {
+            UnmappedReadPolicy::OpenBus => self.data_bus.get(),
+            UnmappedReadPolicy::Zero => 0,
+            UnmappedReadPolicy::Error => {
+                self.error.set(Some(MemoryError::UnmappedRead(address)));
+                self.data_bus.get()
+            }
+        }
+    }
+
+    fn device_failed(&self, address: u16, error: anyhow::Error) {
+        self.error.set(Some(MemoryError::Device {
+            address,
+            message: error.to_string(),
+        }));
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> Result<()> {
+        let Some((index, offset)) = self.resolve(address) else {
+            return Err(anyhow!("Address 0x{address:04X} is not mapped"));
+        };
+
+        match &mut self.regions[index].target {
+            Target::Ram(data) | Target::Rom(data) => {
+                let length = data.len();
+                data[offset % length] = value;
+                Ok(())
+            }
+            Target::Device(_) => Err(anyhow!("Cannot load into device at 0x{address:04X}")),
+            Target::Mirror(_) => unreachable!("Mirrors are resolved to their target"),
+        }
+    }
+}
+
+impl Memory for Bus {
+    fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+            unmapped_read_policy: UnmappedReadPolicy::OpenBus,
+            rom_write_policy: RomWritePolicy::Ignore,
+            data_bus: Cell::new(0),
+            error: Cell::new(None),
+        }
+    }
+
+    fn read_byte(&self, address: u16) -> u8 {
+        let value = match self.resolve(address) {
+            Some((index, offset)) => match &self.regions[index].target {
+                Target::Ram(data) | Target::Rom(data) => data[offset % data.len()],
+                Target::Device(device) => match device.borrow_mut().read(offset as u16) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        self.device_failed(address, error);
+                        self.data_bus.get()
+                    }
+                },
+                Target::Mirror(_) => unreachable!("Mirrors are resolved to their target"),
+            },
+            None => self.read_unmapped(address),
+        };
+
+        self.data_bus.set(value);
+
+        value
+    }
+
+    fn read_short(&self, address: u16) -> u16 {
+        u16::from_le_bytes([
+            self.read_byte(address),
+            self.read_byte(address.wrapping_add(1)),
+        ])
+    }
+
+    // Devices are not read, they show the data bus like unmapped addresses
+    fn peek(&self, address: u16) -> u8 {
+        match self.resolve(address) {
+            Some((index, offset)) => match &self.regions[index].target {
+                Target::Ram(data) | Target::Rom(data) => data[offset % data.len()],
+                Target::Device(_) => self.data_bus.get(),
+                Target::Mirror(_) => unreachable!("Mirrors are resolved to their target"),
+            },
+            None if self.unmapped_read_policy == UnmappedReadPolicy::Zero => 0,
+            None => self.data_bus.get(),
+        }
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.data_bus.set(value);
+
+        let Some((index, offset)) = self.resolve(address) else {
+            return;
+        };
+
+        match &mut self.regions[index].target {
+            Target::Ram(data) => {
+                let length = data.len();
+                data[offset % length] = value;
+            }
+            Target::Device(device) => {
+                if let Err(error) = device.get_mut().write(offset as u16, value) {
+                    self.device_failed(address, error);
+                }
+            }
+            Target::Rom(data) => match self.rom_write_policy.handle(address, value) {
+                Ok(true) => {
+                    let length = data.len();
+                    data[offset % length] = value;
+                }
+                Ok(false) => {}
+                Err(error) => self.error.set(Some(error)),
+            },
+            Target::Mirror(_) => unreachable!("Mirrors are resolved to their target"),
+        }
+    }
+
+    fn write_short(&mut self, address: u16, value: u16) {
+        self.write_byte(address, value as u8);
+        self.write_byte(address.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    fn load_from_file(&mut self, name: &str, address: u16) -> Result<()> {
+        let content = fs::read(name)?;
+
+        self.load(&content, address)
+    }
+
+    fn load(&mut self, executable: &[u8], address: u16) -> Result<()> {
+        if address as usize + executable.len() > 1 << 16 {
+            return Err(anyhow!("Executable does not fit at 0x{address:04X}"));
+        }
+
+        for (i, &byte) in executable.iter().enumerate() {
+            self.poke(address + i as u16, byte)?;
+        }
+
+        Ok(())
+    }
+
+    fn take_error(&mut self) -> Option<MemoryError> {
+        self.error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::cpu::{Cpu, StepError};
+
+    struct Latch {
+        value: u8,
+        reads: Rc<Cell<u32>>,
+    }
+
+    impl Device for Latch {
+        fn read(&mut self, _offset: u16) -> Result<u8> {
+            self.reads.set(self.reads.get() + 1);
+            Ok(self.value)
+        }
+
+        fn write(&mut self, offset: u16, value: u8) -> Result<()> {
+            self.value = value.wrapping_add(offset as u8);
+            Ok(())
+        }
+    }
+
+    // Only has a register at offset 0
+    struct Port;
+
+    impl Device for Port {
+        fn read(&mut self, offset: u16) -> Result<u8> {
+            match offset {
+                0 => Ok(0x42),
+                _ => Err(anyhow!("No register at offset {offset}")),
+            }
+        }
+
+        fn write(&mut self, _offset: u16, _value: u8) -> Result<()> {
+            Err(anyhow!("Port is read only"))
+        }
+    }
+
+    #[test]
+    fn test_device_mapping() {
+        let reads = Rc::new(Cell::new(0));
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0xFFFF);
+        bus.map(
+            0xD000..=0xD0FF,
+            Box::new(Latch {
+                value: 0,
+                reads: reads.clone(),
+            }),
+        );
+
+        bus.write_byte(0xD002, 0x40);
+
+        assert_eq!(bus.read_byte(0xD0FF), 0x42);
+        assert_eq!(reads.get(), 1);
+
+        bus.write_byte(0xCFFF, 0x11);
+        assert_eq!(bus.read_byte(0xCFFF), 0x11);
+    }
+
+    #[test]
+    fn test_device_errors() {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0xFFFF);
+        bus.map(0xD000..=0xD001, Box::new(Port));
+        // LDA $D000, LDA $D001
+        bus.load(&[0xAD, 0x00, 0xD0, 0xAD, 0x01, 0xD0], 0x0200).unwrap();
+        bus.load(&[0x00, 0x02], 0xFFFC).unwrap();
+
+        let mut cpu = Cpu::with_memory(bus);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 0x42);
+
+        // A failed read stops the step and leaves the data bus as it was
+        let error = MemoryError::Device {
+            address: 0xD001,
+            message: "No register at offset 1".to_string(),
+        };
+
+        assert_eq!(cpu.step(), Err(StepError::Bus(error)));
+        assert_eq!(cpu.registers.a, 0xD0);
+
+        cpu.memory.write_byte(0xD000, 0x01);
+        assert!(matches!(
+            cpu.memory.take_error(),
+            Some(MemoryError::Device {
+                address: 0xD000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_peek() {
+        let reads = Rc::new(Cell::new(0));
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x7FFF);
+        bus.map(
+            0xD000..=0xD0FF,
+            Box::new(Latch {
+                value: 0x99,
+                reads: reads.clone(),
+            }),
+        );
+        bus.map_rom(0xFFFC..=0xFFFD, vec![0x00, 0x02]);
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        // JMP $D000
+        bus.load(&[0x4C, 0x00, 0xD0], 0x0200).unwrap();
+        bus.write_byte(0x0010, 0x3C);
+
+        assert_eq!(bus.peek(0x0010), 0x3C);
+        assert_eq!(bus.peek(0xD000), 0x3C);
+        assert_eq!(bus.peek(0x9000), 0x3C);
+        assert_eq!((reads.get(), bus.take_error()), (0, None));
+
+        // Opcode breakpoints peek at the next instruction
+        let mut cpu = Cpu::with_memory(bus);
+        cpu.breakpoints.add_opcode(0x00);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0xD000);
+        assert_eq!((reads.get(), cpu.memory.take_error()), (0, None));
+    }
+
+    #[test]
+    fn test_mirroring() {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x07FF);
+        bus.mirror(0x0800..=0x1FFF, 0x0000..=0x07FF);
+        bus.map_rom(0xC000..=0xFFFF, vec![0xAA, 0xBB]);
+
+        bus.write_byte(0x1801, 0x55);
+
+        assert_eq!(bus.read_byte(0x0001), 0x55);
+        assert_eq!(bus.read_byte(0x0801), 0x55);
+        assert_eq!(bus.read_byte(0xFFFE), 0xAA);
+        assert_eq!(bus.read_byte(0xC001), 0xBB);
+
+        bus.write_byte(0xC000, 0x00);
+        assert_eq!(bus.read_byte(0xC000), 0xAA);
+    }
+
+    #[test]
+    fn test_cyclic_mirrors() {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x00FF);
+        bus.mirror(0x1000..=0x10FF, 0x1000..=0x10FF);
+        bus.mirror(0x2000..=0x20FF, 0x3000..=0x30FF);
+        bus.mirror(0x3000..=0x30FF, 0x2000..=0x20FF);
+        bus.mirror(0x4000..=0x40FF, 0x5000..=0x50FF);
+        bus.mirror(0x5000..=0x50FF, 0x0000..=0x00FF);
+
+        bus.write_byte(0x4010, 0x77);
+
+        assert_eq!(bus.read_byte(0x0010), 0x77);
+        assert!(bus.load(&[0x01], 0x1000).is_err());
+        assert!(bus.load(&[0x01], 0x3000).is_err());
+
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        bus.read_byte(0x2000);
+        assert_eq!(bus.take_error(), Some(MemoryError::UnmappedRead(0x2000)));
+    }
+
+    #[test]
+    fn test_unmapped_reads() {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x00FF);
+        bus.write_byte(0x0010, 0x3C);
+
+        assert_eq!(bus.read_byte(0x0010), 0x3C);
+        assert_eq!(bus.read_byte(0x4000), 0x3C);
+
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Zero);
+        assert_eq!(bus.read_byte(0x4000), 0x00);
+        assert_eq!(bus.take_error(), None);
+
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        bus.read_byte(0x4000);
+        assert_eq!(bus.take_error(), Some(MemoryError::UnmappedRead(0x4000)));
+        assert_eq!(bus.take_error(), None);
+    }
+
+    #[test]
+    fn test_cpu_on_bus() {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x7FFF);
+        bus.map_rom(0x8000..=0xFFFF, vec![0; 0x8000]);
+        // INX, INX, TXA
+        bus.load(&[0xE8, 0xE8, 0x8A], 0x8000).unwrap();
+        bus.load(&[0x00, 0x80], 0xFFFC).unwrap();
+
+        let mut cpu = Cpu::with_memory(bus);
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(cpu.registers.a, 2);
+
+        cpu.memory.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        cpu.memory.regions.clear();
+
+        assert!(cpu.step().is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use super::{BusCycle, BusDirection, Cpu, StopReason};
+use crate::expression::Expression;
+use crate::instruction::{Instruction, InstructionType};
+use crate::memory::Memory;
+use crate::registers::Registers;
+
+/// The accesses a watchpoint reacts to.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+struct Watchpoint {
+    range: RangeInclusive<u16>,
+    kind: WatchKind,
+}
+
+impl Watchpoint {
+    fn matches(&self, access: &BusCycle) -> bool {
+        let direction = match self.kind {
+            WatchKind::Read => access.direction == BusDirection::Read,
+            WatchKind::Write => access.direction == BusDirection::Write,
+            WatchKind::ReadWrite => true,
+        };
+
+        direction && self.range.contains(&access.address)
+    }
+}
+
+struct Condition {
+    expression: Expression,
+    pc: Option<u16>,
+    hit_count: u32,
+    hits: u32,
+    was_true: bool,
+}
+
+impl Condition {
+    // Counts a hit and returns whether the breakpoint stops
+    fn check<M: Memory>(&mut self, registers: &Registers, memory: &M) -> bool {
+        if self.pc.is_some_and(|pc| pc != registers.pc) {
+            return false;
+        }
+
+        let is_true = self.expression.is_true(registers, memory);
+        let became_true = is_true && (self.pc.is_some() || !self.was_true);
+
+        self.was_true = is_true;
+
+        if became_true {
+            self.hits += 1;
+        }
+
+        became_true && self.hits >= self.hit_count
+    }
+}
+
+/// Execution breakpoints, conditions and memory watchpoints. Breakpoints stop a
+/// run before the instruction is executed, watchpoints after the instruction
+/// that made the access. Dummy accesses are seen by watchpoints as well.
+#[derive(Default)]
+pub struct Breakpoints {
+    pcs: HashSet<u16>,
+    opcodes: HashSet<u8>,
+    instruction_types: HashSet<InstructionType>,
+    watchpoints: Vec<Watchpoint>,
+    conditions: Vec<Condition>,
+}
+
+impl Breakpoints {
+    pub fn new() -> Breakpoints {
+        Breakpoints::default()
+    }
+
+    pub fn add_pc(&mut self, pc: u16) {
+        self.pcs.insert(pc);
+    }
+
+    pub fn remove_pc(&mut self, pc: u16) -> bool {
+        self.pcs.remove(&pc)
+    }
+
+    pub fn add_opcode(&mut self, opcode: u8) {
+        self.opcodes.insert(opcode);
+    }
+
+    pub fn remove_opcode(&mut self, opcode: u8) -> bool {
+        self.opcodes.remove(&opcode)
+    }
+
+    pub fn add_instruction_type(&mut self, instruction_type: InstructionType) {
+        self.instruction_types.insert(instruction_type);
+    }
+
+    pub fn remove_instruction_type(&mut self, instruction_type: InstructionType) -> bool {
+        self.instruction_types.remove(&instruction_type)
+    }
+
+    /// Adds a watchpoint on the addresses in `range` and returns its index.
+    pub fn add_watchpoint(&mut self, range: RangeInclusive<u16>, kind: WatchKind) -> usize {
+        self.watchpoints.push(Watchpoint { range, kind });
+
+        self.watchpoints.len() - 1
+    }
+
+    /// Removes the watchpoint at `index`, shifting the indices of later ones.
+    pub fn remove_watchpoint(&mut self, index: usize) -> bool {
+        if index >= self.watchpoints.len() {
+            return false;
+        }
+
+        self.watchpoints.remove(index);
+
+        true
+    }
+
+    /// Adds a breakpoint on `expression` and returns its index. It stops
+    /// on the `hit_count`th hit and every one after. With a pc the expression
+    /// is checked whenever the instruction at that address comes next,
+    /// otherwise after every instruction and only hit when it becomes true.
+    pub fn add_condition(
+        &mut self,
+        expression: Expression,
+        pc: Option<u16>,
+        hit_count: u32,
+    ) -> usize {
+        self.conditions.push(Condition {
+            expression,
+            pc,
+            hit_count,
+            hits: 0,
+            was_true: false,
+        });
+
+        self.conditions.len() - 1
+    }
+
+    /// Removes the condition at `index`, shifting the indices of later ones.
+    pub fn remove_condition(&mut self, index: usize) -> bool {
+        if index >= self.conditions.len() {
+            return false;
+        }
+
+        self.conditions.remove(index);
+
+        true
+    }
+
+    /// How often the condition at `index` was hit so far.
+    pub fn condition_hits(&self, index: usize) -> Option<u32> {
+        Some(self.conditions.get(index)?.hits)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Breakpoints::default();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pcs.is_empty()
+            && self.opcodes.is_empty()
+            && self.instruction_types.is_empty()
+            && self.watchpoints.is_empty()
+            && self.conditions.is_empty()
+    }
+
+    // Every condition is checked, so that all of them count their hits
+    fn check_conditions<M: Memory>(&mut self, registers: &Registers, memory: &M) -> bool {
+        let mut hit = false;
+
+        for condition in self.conditions.iter_mut() {
+            hit |= condition.check(registers, memory);
+        }
+
+        hit
+    }
+
+    fn watched_access(&self, accesses: &[BusCycle]) -> Option<BusCycle> {
+        accesses
+            .iter()
+            .find(|access| self.watchpoints.iter().any(|watch| watch.matches(access)))
+            .copied()
+    }
+
+    fn breaks_on_instruction(&self) -> bool {
+        !self.opcodes.is_empty() || !self.instruction_types.is_empty()
+    }
+
+    fn breaks_before<M: Memory + 'static>(&self, pc: u16, instruction: &Instruction<M>) -> bool {
+        self.pcs.contains(&pc)
+            || self.opcodes.contains(&instruction.opcode)
+            || self.instruction_types.contains(&instruction.instruction_type)
+    }
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// The breakpoint or watchpoint hit by the last step, if any.
+    pub fn break_hit(&self) -> Option<StopReason> {
+        self.break_hit
+    }
+
+    // Runs after every completed instruction. Watchpoints look at the accesses
+    // that were just made, breakpoints at the instruction that comes next.
+    pub(super) fn check_breakpoints(&mut self) {
+        if self.breakpoints.is_empty() {
+            return;
+        }
+
+        let pc = self.registers.pc;
+        let condition_hit = self.breakpoints.check_conditions(&self.registers, &self.memory);
+
+        if let Some(access) = self.breakpoints.watched_access(&self.bus_cycles) {
+            self.break_hit = Some(StopReason::Watchpoint { pc, access });
+            return;
+        }
+
+        if self.breaks_before_pc() || condition_hit {
+            self.break_hit = Some(StopReason::Breakpoint { pc });
+            self.reported_break = Some((pc, self.cycles));
+        }
+    }
+
+    // Runs before the first instruction of a run, so breakpoints at its start
+    // pc are hit as well. The breakpoint the cpu last stopped at is skipped to
+    // let a run resume from it.
+    pub(super) fn check_start_breakpoint(&mut self) -> Option<StopReason> {
+        let pc = self.registers.pc;
+
+        if self.breakpoints.is_empty() || self.reported_break == Some((pc, self.cycles)) {
+            return None;
+        }
+
+        if !self.breaks_before_pc() {
+            return None;
+        }
+
+        self.break_hit = Some(StopReason::Breakpoint { pc });
+        self.reported_break = Some((pc, self.cycles));
+
+        self.break_hit
+    }
+
+    // The opcode is peeked without a bus access
+    fn breaks_before_pc(&self) -> bool {
+        let pc = self.registers.pc;
+
+        match self.breakpoints.breaks_on_instruction() {
+            true => {
+                let opcode = self.memory.peek(pc);
+                let instruction = &self.instructions()[opcode as usize];
+
+                self.breakpoints.breaks_before(pc, instruction)
+            }
+            false => self.breakpoints.pcs.contains(&pc),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cpu::{Cpu, HaltReason};
+use crate::memory::Memory;
+use crate::util::get_bit;
+
+impl<M: Memory + 'static> Cpu<M> {
+    pub fn bra(&mut self) {
+        self.branch_if(true);
+    }
+
+    pub fn phx(&mut self) {
+        self.push_byte(self.registers.x);
+    }
+
+    pub fn phy(&mut self) {
+        self.push_byte(self.registers.y);
+    }
+
+    pub fn plx(&mut self) {
+        self.read_stack();
+        self.registers.x = self.pop_byte();
+
+        self.update_zero_flag(self.registers.x);
+        self.update_negative_flag(self.registers.x);
+    }
+
+    pub fn ply(&mut self) {
+        self.read_stack();
+        self.registers.y = self.pop_byte();
+
+        self.update_zero_flag(self.registers.y);
+        self.update_negative_flag(self.registers.y);
+    }
+
+    pub fn stz(&mut self) {
+        let address = self.get_operand_address();
+
+        self.write(address, 0);
+    }
+
+    pub fn tsb(&mut self) {
+        let value = self.get_operand_value();
+
+        self.update_zero_flag(value & self.registers.a);
+        self.write_modified(value | self.registers.a);
+    }
+
+    pub fn trb(&mut self) {
+        let value = self.get_operand_value();
+
+        self.update_zero_flag(value & self.registers.a);
+        self.write_modified(value & !self.registers.a);
+    }
+
+    pub fn rmb(&mut self) {
+        let bit = self.opcode_bit();
+
+        self.replace_accumulator_or_memory(|value| value & !(1 << bit));
+    }
+
+    pub fn smb(&mut self) {
+        let bit = self.opcode_bit();
+
+        self.replace_accumulator_or_memory(|value| value | (1 << bit));
+    }
+
+    pub fn bbr(&mut self) {
+        self.branch_on_bit(false);
+    }
+
+    pub fn bbs(&mut self) {
+        self.branch_on_bit(true);
+    }
+
+    pub fn wai(&mut self) {
+        self.read(self.registers.pc);
+
+        self.waiting = true;
+    }
+
+    pub fn stp(&mut self) {
+        self.read(self.registers.pc);
+
+        self.halt_reason = Some(HaltReason::Stopped {
+            address: self.registers.pc.wrapping_sub(1),
+        });
+    }
+
+    // The bit instructions encode the bit number in the high nibble of the opcode
+    fn opcode_bit(&self) -> u8 {
+        (self.opcode >> 4) & 0x07
+    }
+
+    fn branch_on_bit(&mut self, set: bool) {
+        let bit = self.opcode_bit();
+        let address = self.get_operand_address();
+
+        let value = self.read(address);
+        self.read(address);
+
+        let offset = self.fetch_byte() as i8;
+        let pc = self.registers.pc;
+        let target = pc.wrapping_add_signed(offset.into());
+
+        self.page_crossed = (pc & 0xFF00) != (target & 0xFF00);
+        self.operand_address = Some(target);
+
+        self.branch_if(get_bit(value, bit) == set);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::mem;
+
+use super::{Cpu, StepOutcome};
+use crate::memory::Memory;
+
+/// What the cpu does after a hook returns.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HookAction {
+    Continue,
+    Exit(i32), // Ends the step with `StepOutcome::Exited` and a run with `StopReason::Exited`
+}
+
+/// A host function that services the program, with access to the registers
+/// and memory of the cpu. Hooks run in `step` and the runs built on it,
+/// `tick` does not run them.
+pub type Hook<M> = Box<dyn FnMut(&mut Cpu<M>) -> HookAction>;
+
+#[derive(Copy, Clone, PartialEq)]
+enum HookKey {
+    Address(u16),
+    Opcode(u8),
+}
+
+pub(super) struct Hooks<M: Memory + 'static> {
+    addresses: HashMap<u16, Hook<M>>,
+    opcodes: HashMap<u8, Hook<M>>,
+    running: Option<HookKey>, // The hook being called, it is out of its map meanwhile
+    running_removed: bool,
+}
+
+impl<M: Memory + 'static> Default for Hooks<M> {
+    fn default() -> Hooks<M> {
+        Hooks {
+            addresses: HashMap::new(),
+            opcodes: HashMap::new(),
+            running: None,
+            running_removed: false,
+        }
+    }
+}
+
+impl<M: Memory + 'static> Hooks<M> {
+    // Keeps the hook being called from being put back. Returns whether it
+    // was still in place.
+    fn remove_running(&mut self, key: HookKey) -> bool {
+        if self.running != Some(key) {
+            return false;
+        }
+
+        !mem::replace(&mut self.running_removed, true)
+    }
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// Runs `hook` whenever execution reaches `address`. Afterwards the
+    /// instruction at the pc the hook left is executed in the same step, so a
+    /// hook can be placed on an RTS to return to the caller.
+    pub fn add_address_hook(
+        &mut self,
+        address: u16,
+        hook: impl FnMut(&mut Cpu<M>) -> HookAction + 'static,
+    ) {
+        self.hooks.addresses.insert(address, Box::new(hook));
+    }
+
+    /// Removes the hook at `address`. A hook may remove or replace itself
+    /// while it is called.
+    pub fn remove_address_hook(&mut self, address: u16) -> bool {
+        let removed = self.hooks.addresses.remove(&address).is_some();
+
+        self.hooks.remove_running(HookKey::Address(address)) || removed
+    }
+
+    /// Runs `hook` instead of the instruction with `opcode` once the opcode
+    /// has been fetched, which takes the only cycle of the hook. The pc points
+    /// past the opcode, so the hook can read inline arguments and skip them.
+    /// Opcodes that the chip does not define are the natural choice.
+    pub fn add_opcode_hook(
+        &mut self,
+        opcode: u8,
+        hook: impl FnMut(&mut Cpu<M>) -> HookAction + 'static,
+    ) {
+        self.hooks.opcodes.insert(opcode, Box::new(hook));
+    }
+
+    /// Removes the hook for `opcode`. A hook may remove or replace itself
+    /// while it is called.
+    pub fn remove_opcode_hook(&mut self, opcode: u8) -> bool {
+        let removed = self.hooks.opcodes.remove(&opcode).is_some();
+
+        self.hooks.remove_running(HookKey::Opcode(opcode)) || removed
+    }
+
+    // Runs before the instruction of a step. Returns the outcome of the step
+    // if the hook at the pc ended it. Address hooks take no cycles and make no
+    // bus accesses.
+    pub(super) fn run_address_hook(&mut self) -> Option<StepOutcome> {
+        let pc = self.registers.pc;
+        let hook = self.hooks.addresses.remove(&pc)?;
+
+        match self.call_hook(HookKey::Address(pc), hook) {
+            HookAction::Continue => None,
+            HookAction::Exit(code) => Some(StepOutcome::Exited { code }),
+        }
+    }
+
+    // Runs after the opcode fetch of a step. Returns the outcome of the step
+    // if a hook took the place of the instruction.
+    pub(super) fn run_opcode_hook(&mut self, opcode: u8) -> Option<StepOutcome> {
+        let hook = self.hooks.opcodes.remove(&opcode)?;
+
+        self.cycles += 1;
+
+        Some(match self.call_hook(HookKey::Opcode(opcode), hook) {
+            HookAction::Continue => StepOutcome::Executed { opcode, cycles: 1 },
+            HookAction::Exit(code) => StepOutcome::Exited { code },
+        })
+    }
+
+    // The hook is put back after the call unless it removed itself, a
+    // replacement it added is kept instead of it
+    fn call_hook(&mut self, key: HookKey, mut hook: Hook<M>) -> HookAction {
+        let running = self.hooks.running.replace(key);
+        let running_removed = mem::take(&mut self.hooks.running_removed);
+
+        let action = hook(self);
+
+        if !self.hooks.running_removed {
+            match key {
+                HookKey::Address(address) => {
+                    self.hooks.addresses.entry(address).or_insert(hook);
+                }
+                HookKey::Opcode(opcode) => {
+                    self.hooks.opcodes.entry(opcode).or_insert(hook);
+                }
+            }
+        }
+
+        self.hooks.running = running;
+        self.hooks.running_removed = running_removed;
+
+        action
+    }
+}
@@ -1,42 +1,60 @@
-use crate::cpu::{Cpu, Interrupt};
+use crate::cpu::{Cpu, HaltReason, Interrupt, BREAK, UNUSED};
+use crate::instruction::AddressingMode;
 use crate::memory::Memory;
-use crate::registers::{Flag, Flags};
+use crate::registers::Flag;
 use crate::util::get_bit;
 
-impl Cpu {
+// Shifts and rotates for `replace_accumulator_or_memory_with_carry`, taking the
+// value and the carry and returning the result and the new carry
+pub(super) fn shift_left(value: u8, _carry: u8) -> (u8, bool) {
+    (value << 1, value & 0x80 != 0)
+}
+
+pub(super) fn rotate_left(value: u8, carry: u8) -> (u8, bool) {
+    ((value << 1) | carry, value & 0x80 != 0)
+}
+
+pub(super) fn shift_right(value: u8, _carry: u8) -> (u8, bool) {
+    (value >> 1, value & 1 == 1)
+}
+
+pub(super) fn rotate_right(value: u8, carry: u8) -> (u8, bool) {
+    ((value >> 1) | (carry << 7), value & 1 == 1)
+}
+
+impl<M: Memory + 'static> Cpu<M> {
     pub fn brk(&mut self) {
-        self.registers.flags.set(Flag::Break, true);
-        self.registers.pc += 1;
-        self.handle_interrupt(Interrupt::Maskable);
+        // The byte following BRK is skipped
+        self.registers.pc = self.registers.pc.wrapping_add(1);
+        self.handle_interrupt(Interrupt::Break);
     }
 
     pub fn ora(&mut self) {
-        let operand = self.get_operand_value().expect("Could not get operand");
+        let operand = self.get_operand_value();
 
-        self.registers.a = self.registers.a | operand;
+        self.registers.a |= operand;
 
         self.update_zero_flag(self.registers.a);
         self.update_negative_flag(self.registers.a);
     }
 
     pub fn kil(&mut self) {
-        todo!()
+        self.halt_reason = Some(HaltReason::Jammed {
+            opcode: self.opcode,
+            address: self.registers.pc.wrapping_sub(1),
+        });
     }
 
     pub fn asl(&mut self) {
-        self.replace_accumulator_or_memory_with_carry(|value, _| (value << 1, value & 0x80 != 0))
+        self.replace_accumulator_or_memory_with_carry(shift_left);
     }
 
     pub fn php(&mut self) {
-        let mut status = self.registers.flags;
-
-        status.set(Flag::Break, true);
-
-        self.push_byte(status.0);
+        self.push_byte(self.registers.flags.0 | BREAK | UNUSED);
     }
 
     pub fn bpl(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Negative) == false);
+        self.branch_if(!self.registers.flags.get(Flag::Negative));
     }
 
     pub fn clc(&mut self) {
@@ -44,45 +62,50 @@ impl Cpu {
     }
 
     pub fn jsr(&mut self) {
-        self.push_short(self.registers.pc + 1);
+        let low = self.fetch_byte();
+
+        self.read_stack();
+
+        // The return address points at the last byte of the JSR instruction
+        self.push_short(self.registers.pc);
 
-        self.branch_if(true);
+        let high = self.read(self.registers.pc);
+
+        self.registers.pc = u16::from_le_bytes([low, high]);
     }
 
     pub fn and(&mut self) {
-        let value = self.get_operand_value().expect("Could not get operand value");
+        let value = self.get_operand_value();
 
-        self.registers.a = self.registers.a & value;
+        self.registers.a &= value;
 
         self.update_zero_flag(self.registers.a);
         self.update_negative_flag(self.registers.a);
     }
 
     pub fn bit(&mut self) {
-        let value = self.get_operand_value().expect("Could not get operand value");
+        let value = self.get_operand_value();
 
-        self.registers.flags.set(Flag::Negative, get_bit(value, Flag::Negative as u8));
-        self.registers.flags.set(Flag::Overflow, get_bit(value, Flag::Overflow as u8));
+        // BIT # of the 65C02 only affects the zero flag
+        if self.instruction().mode != AddressingMode::Immediate {
+            self.registers.flags.set(Flag::Negative, get_bit(value, Flag::Negative as u8));
+            self.registers.flags.set(Flag::Overflow, get_bit(value, Flag::Overflow as u8));
+        }
 
         self.update_zero_flag(value & self.registers.a);
     }
 
     pub fn rol(&mut self) {
-        self.replace_accumulator_or_memory_with_carry(|value, carry_in| {
-            ((value << 1) | carry_in, value & 0x80 != 0)
-        })
+        self.replace_accumulator_or_memory_with_carry(rotate_left);
     }
 
     pub fn plp(&mut self) {
-        let current_break_status = self.registers.flags.get(Flag::Break);
-
-        self.registers.flags = Flags(self.pop_byte());
-
-        self.registers.flags.set(Flag::Break, current_break_status);
+        self.read_stack();
+        self.pull_flags();
     }
 
     pub fn bmi(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Negative) == true);
+        self.branch_if(self.registers.flags.get(Flag::Negative));
     }
 
     pub fn sec(&mut self) {
@@ -90,22 +113,22 @@ impl Cpu {
     }
 
     pub fn rti(&mut self) {
-        self.registers.flags = Flags(self.pop_byte());
-        self.registers.flags.set(Flag::Break, false);
+        self.read_stack();
+        self.pull_flags();
         self.registers.pc = self.pop_short();
     }
 
     pub fn eor(&mut self) {
-        let value = self.get_operand_value().expect("Could not get operand value");
+        let value = self.get_operand_value();
 
-        self.registers.a = self.registers.a ^ value;
+        self.registers.a ^= value;
 
         self.update_zero_flag(self.registers.a);
         self.update_negative_flag(self.registers.a);
     }
 
     pub fn lsr(&mut self) {
-        self.replace_accumulator_or_memory_with_carry(|value, _| (value >> 1, value & 1 == 1))
+        self.replace_accumulator_or_memory_with_carry(shift_right);
     }
 
     pub fn pha(&mut self) {
@@ -113,13 +136,13 @@ impl Cpu {
     }
 
     pub fn jmp(&mut self) {
-        let new_pc = self.get_operand_address().expect("New address should be valid");
+        let new_pc = self.get_operand_address();
 
         self.registers.pc = new_pc;
     }
 
     pub fn bvc(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Overflow) == false);
+        self.branch_if(!self.registers.flags.get(Flag::Overflow));
     }
 
     pub fn cli(&mut self) {
@@ -127,31 +150,26 @@ impl Cpu {
     }
 
     pub fn rts(&mut self) {
-        let return_address = self.pop_short() + 1;
+        self.read_stack();
 
-        self.registers.pc = return_address;
+        let return_address = self.pop_short();
+
+        self.read(return_address);
+        self.registers.pc = return_address.wrapping_add(1);
     }
 
     pub fn adc(&mut self) {
-        let value = self.get_operand_value().expect("Should get a valid operand");
-
-        if self.registers.flags.get(Flag::Decimal) {
-            self.add_bcd(value);
-        } else {
-            self.add_binary(value);
-        }
+        let value = self.get_operand_value();
 
-        self.update_negative_flag(self.registers.a);
-        self.update_zero_flag(self.registers.a);
+        self.add_with_carry(value);
     }
 
     pub fn ror(&mut self) {
-        self.replace_accumulator_or_memory_with_carry(|value, carry_in| {
-            ((value >> 1) | (carry_in << 7), value & 1 == 1)
-        })
+        self.replace_accumulator_or_memory_with_carry(rotate_right);
     }
 
     pub fn pla(&mut self) {
+        self.read_stack();
         self.registers.a = self.pop_byte();
 
         self.update_zero_flag(self.registers.a);
@@ -159,7 +177,7 @@ impl Cpu {
     }
 
     pub fn bvs(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Overflow) == true);
+        self.branch_if(self.registers.flags.get(Flag::Overflow));
     }
 
     pub fn sei(&mut self) {
@@ -167,25 +185,25 @@ impl Cpu {
     }
 
     pub fn sta(&mut self) {
-        let address = self.get_operand_address().expect("Could not get operand");
+        let address = self.get_operand_address();
 
-        self.memory.write_byte(address, self.registers.a);
+        self.write(address, self.registers.a);
     }
 
     pub fn sty(&mut self) {
-        let address = self.get_operand_address().expect("Could not get operand");
+        let address = self.get_operand_address();
 
-        self.memory.write_byte(address, self.registers.y);
+        self.write(address, self.registers.y);
     }
 
     pub fn stx(&mut self) {
-        let address = self.get_operand_address().expect("Could not get operand");
+        let address = self.get_operand_address();
 
-        self.memory.write_byte(address, self.registers.y);
+        self.write(address, self.registers.x);
     }
 
     pub fn dey(&mut self) {
-        self.registers.y = self.registers.y - 1;
+        self.registers.y = self.registers.y.wrapping_sub(1);
 
         self.update_zero_flag(self.registers.y);
         self.update_negative_flag(self.registers.y);
@@ -199,7 +217,7 @@ impl Cpu {
     }
 
     pub fn bcc(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Carry) == false)
+        self.branch_if(!self.registers.flags.get(Flag::Carry))
     }
 
     pub fn tya(&mut self) {
@@ -214,7 +232,7 @@ impl Cpu {
     }
 
     pub fn ldy(&mut self) {
-        let operand = self.get_operand_value().expect("Could not get operand");
+        let operand = self.get_operand_value();
 
         self.registers.y = operand;
 
@@ -223,7 +241,7 @@ impl Cpu {
     }
 
     pub fn lda(&mut self) {
-        let operand = self.get_operand_value().expect("Could not get operand");
+        let operand = self.get_operand_value();
 
         self.registers.a = operand;
 
@@ -232,7 +250,7 @@ impl Cpu {
     }
 
     pub fn ldx(&mut self) {
-        let operand = self.get_operand_value().expect("Could not get operand");
+        let operand = self.get_operand_value();
 
         self.registers.x = operand;
 
@@ -243,8 +261,8 @@ impl Cpu {
     pub fn tay(&mut self) {
         self.registers.y = self.registers.a;
 
-        self.update_zero_flag(self.registers.x);
-        self.update_negative_flag(self.registers.x);
+        self.update_zero_flag(self.registers.y);
+        self.update_negative_flag(self.registers.y);
     }
 
     pub fn tax(&mut self) {
@@ -278,31 +296,28 @@ impl Cpu {
     }
 
     pub fn dec(&mut self) {
-        let address = self.get_operand_address().expect("Could not get operand address");
-
-        let new_value = self.memory.read_byte(address) - 1;
+        let new_value = self.replace_accumulator_or_memory(|value| value.wrapping_sub(1));
 
-        self.memory.write_byte(address, new_value);
         self.update_zero_flag(new_value);
         self.update_negative_flag(new_value);
     }
 
     pub fn iny(&mut self) {
-        self.registers.y = self.registers.y + 1;
+        self.registers.y = self.registers.y.wrapping_add(1);
 
         self.update_zero_flag(self.registers.y);
         self.update_negative_flag(self.registers.y);
     }
 
     pub fn dex(&mut self) {
-        self.registers.x = self.registers.x - 1;
+        self.registers.x = self.registers.x.wrapping_sub(1);
 
         self.update_zero_flag(self.registers.x);
         self.update_negative_flag(self.registers.x);
     }
 
     pub fn bne(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Zero) == false);
+        self.branch_if(!self.registers.flags.get(Flag::Zero));
     }
 
     pub fn cld(&mut self) {
@@ -314,42 +329,44 @@ impl Cpu {
     }
 
     pub fn sbc(&mut self) {
-        let value = self.get_operand_value().expect("Should get a valid operand");
-
-        if self.registers.flags.get(Flag::Decimal) {
-            self.sbc_bcd(value);
-        } else {
-            self.sbc_binary(value);
-        }
+        let value = self.get_operand_value();
 
-        self.update_negative_flag(self.registers.a);
-        self.update_zero_flag(self.registers.a);
+        self.subtract_with_carry(value);
     }
 
     pub fn inc(&mut self) {
-        let address = self.get_operand_address().expect("Could not get operand address");
+        let new_value = self.replace_accumulator_or_memory(|value| value.wrapping_add(1));
 
-        let new_value = self.memory.read_byte(address) + 1;
-
-        self.memory.write_byte(address, new_value);
         self.update_zero_flag(new_value);
         self.update_negative_flag(new_value);
     }
 
     pub fn inx(&mut self) {
-        self.registers.x = self.registers.x + 1;
+        self.registers.x = self.registers.x.wrapping_add(1);
 
         self.update_zero_flag(self.registers.x);
         self.update_negative_flag(self.registers.x);
     }
 
-    pub fn nop(&mut self) {}
+    pub fn nop(&mut self) {
+        let cycles = self.instruction().cycles as usize;
+
+        // The multi byte NOPs read their operand, the eight cycle NOP of the
+        // 65C02 keeps on reading it
+        if self.operand_address.is_some() {
+            self.get_operand_value();
+
+            while self.bus_cycles.len() < cycles {
+                self.get_operand_value();
+            }
+        }
+    }
 
     pub fn beq(&mut self) {
-        self.branch_if(self.registers.flags.get(Flag::Zero) == true);
+        self.branch_if(self.registers.flags.get(Flag::Zero));
     }
 
     pub fn sed(&mut self) {
         self.registers.flags.set(Flag::Decimal, true);
     }
-}
\ No newline at end of file
+}
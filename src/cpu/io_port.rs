@@ -0,0 +1,25 @@
+/// The on-chip I/O port of the 6510. The data direction register at $0000
+/// makes a pin an output when its bit is set, the data register at $0001
+/// holds the levels driven on the outputs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IoPort {
+    pub direction: u8,
+    pub data: u8,
+    pub input: u8, // Levels applied to the pins by the host
+}
+
+impl IoPort {
+    // All pins start out as inputs that are pulled high
+    pub(super) fn new() -> IoPort {
+        IoPort {
+            direction: 0x00,
+            data: 0x00,
+            input: 0xFF,
+        }
+    }
+
+    /// Levels of the pins, which is also what reading $0001 returns.
+    pub fn pins(&self) -> u8 {
+        (self.data & self.direction) | (self.input & !self.direction)
+    }
+}
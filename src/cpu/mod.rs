@@ -1,18 +1,45 @@
+mod breakpoints;
+mod cmos;
+mod hooks;
 mod instructions;
+mod io_port;
+mod run;
+mod tick;
+mod trace;
+mod undocumented;
 
+use std::error::Error;
 use std::fmt::{self, Debug, Display, Formatter};
-use std::thread::current;
 
 use anyhow::Result;
 use indent::indent_all_by;
-use log;
 
 use crate::default_memory::DefaultMemory;
 use crate::instruction::{AddressingMode, Instruction, InstructionType};
-use crate::instruction_table::INSTRUCTIONS;
-use crate::memory::Memory;
+use crate::instruction_table::InstructionTable;
+use crate::memory::{Memory, MemoryError};
 use crate::registers::{Flag, Flags, Registers};
-use crate::util::{get_bit, FromTwosComplementBits};
+use crate::util::FromTwosComplementBits;
+
+pub use breakpoints::{Breakpoints, WatchKind};
+use hooks::Hooks;
+pub use hooks::{Hook, HookAction};
+pub use io_port::IoPort;
+pub use run::StopReason;
+use run::TrapDetector;
+use tick::TickState;
+pub use trace::{TraceEntry, TraceWriter, Tracer};
+
+/// The chip that is emulated. It decides the instruction set and the
+/// behavioural differences between the parts.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Variant {
+    #[default]
+    Nmos6502,
+    Ricoh2A03, // NES cpu, an NMOS 6502 whose decimal mode is disconnected
+    Mos6510,   // C64 cpu, an NMOS 6502 with an I/O port at $0000 and $0001
+    Cmos65C02, // WDC 65C02 including the Rockwell bit instructions
+}
 
 #[derive(PartialEq, Debug)]
 pub enum Voltage {
@@ -20,37 +47,144 @@ pub enum Voltage {
     High,
 }
 
-#[derive(Debug)]
-pub(crate) enum Operand {
-    Byte(u8),
-    Short(u16),
-}
-
 enum Interrupt {
     Maskable,
     NonMaskable,
+    Break,
+}
+
+// Bits 4 and 5 of the status register only exist in copies pushed to the stack
+const BREAK: u8 = 1 << Flag::Break as u8;
+const UNUSED: u8 = 1 << 5;
+
+const NMI_VECTOR: u16 = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const IRQ_VECTOR: u16 = 0xFFFE;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BusDirection {
+    Read,
+    Write,
+}
+
+/// A single bus access. The NMOS 6502 accesses the bus exactly once per cycle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BusCycle {
+    pub address: u16,
+    pub value: u8,
+    pub direction: BusDirection,
+}
+
+/// Why the cpu stopped executing. Only a reset recovers from a halt.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HaltReason {
+    Jammed { opcode: u8, address: u16 }, // A KIL opcode locked up the cpu
+    Stopped { address: u16 },            // STP stopped the clock of a 65C02
+}
+
+impl Display for HaltReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HaltReason::Jammed { opcode, address } => {
+                write!(f, "Cpu jammed by opcode 0x{opcode:02X} at 0x{address:04X}")
+            }
+            HaltReason::Stopped { address } => write!(f, "Cpu stopped at 0x{address:04X}"),
+        }
+    }
+}
+
+impl Error for HaltReason {}
+
+/// What a successful step did.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StepOutcome {
+    Executed { opcode: u8, cycles: u32 }, // Cycles include an interrupt taken afterwards
+    Interrupted { cycles: u32 },          // A waiting 65C02 took an interrupt
+    Waiting,                              // A 65C02 idled for a cycle in WAI
+    Exited { code: i32 },                 // A hook ended the program
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepError {
+    Halted(HaltReason),
+    InvalidOpcode { opcode: u8, address: u16 }, // Only raised in strict mode
+    Bus(MemoryError),
+}
+
+impl Display for StepError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::Halted(reason) => write!(f, "{reason}"),
+            StepError::InvalidOpcode { opcode, address } => {
+                write!(f, "Invalid opcode 0x{opcode:02X} at 0x{address:04X}")
+            }
+            StepError::Bus(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for StepError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StepError::Halted(reason) => Some(reason),
+            StepError::InvalidOpcode { .. } => None,
+            StepError::Bus(error) => Some(error),
+        }
+    }
 }
 
-pub struct Cpu<Memory = DefaultMemory> {
+impl From<HaltReason> for StepError {
+    fn from(reason: HaltReason) -> Self {
+        StepError::Halted(reason)
+    }
+}
+
+impl From<MemoryError> for StepError {
+    fn from(error: MemoryError) -> Self {
+        StepError::Bus(error)
+    }
+}
+
+pub struct Cpu<M: Memory + 'static = DefaultMemory> {
     pub registers: Registers,
-    pub memory: Memory,
+    pub memory: M,
     pub cycles: u32,
-    pub current_instruction: Option<&'static Instruction>,
+    pub current_instruction: Option<&'static Instruction<M>>,
+    pub breakpoints: Breakpoints,
+    opcode: u8,
+    variant: Variant,
+    undocumented_opcodes: bool,
+    strict: bool,
     irq_line: Voltage,
+    nmi_line: Voltage,
     nmi_edge: bool,
+    operand_address: Option<u16>,
+    page_crossed: bool,
+    branch_taken: bool,
+    bus_cycles: Vec<BusCycle>,
+    replay: Option<Vec<BusCycle>>,
+    tick_state: Option<TickState<M>>,
+    halt_reason: Option<HaltReason>,
+    break_hit: Option<StopReason>,
+    reported_break: Option<(u16, u32)>, // The pc and cycles of the last breakpoint hit
+    waiting: bool,
+    io_port: Option<IoPort>,
+    tracer: Option<Box<dyn Tracer>>,
+    trap_detector: Option<TrapDetector>,
+    hooks: Hooks<M>,
 }
 
-impl Display for Cpu {
+impl<M: Memory + 'static> Display for Cpu<M> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Cpu:\n")?;
-        write!(f, "    cycles = {}\n\n", self.cycles);
+        writeln!(f, "Cpu:")?;
+        writeln!(f, "    cycles = {}\n", self.cycles)?;
 
-        write!(f, "{}", indent_all_by(4, self.registers.to_string()));
+        write!(f, "{}", indent_all_by(4, self.registers.to_string()))?;
         Ok(())
     }
 }
 
-impl Debug for Cpu {
+impl<M: Memory + 'static> Debug for Cpu<M> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Cpu")
             .field("registers", &self.registers)
@@ -62,263 +196,704 @@ impl Debug for Cpu {
 
 impl Cpu {
     pub fn new() -> Cpu {
+        Cpu::with_memory(DefaultMemory::new())
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        Cpu::new()
+    }
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// Creates a cpu around an already set up memory, so that devices or ROMs
+    /// can be in place before the reset vector is read.
+    pub fn with_memory(memory: M) -> Cpu<M> {
+        Cpu::with_variant(memory, Variant::Nmos6502)
+    }
+
+    pub fn with_variant(memory: M, variant: Variant) -> Cpu<M> {
         let mut cpu = Cpu {
             registers: Registers::new(),
-            memory: Memory::new(),
+            memory,
             cycles: 0,
             current_instruction: None,
+            breakpoints: Breakpoints::new(),
+            opcode: 0,
+            variant,
+            undocumented_opcodes: false,
+            strict: false,
             irq_line: Voltage::High,
+            nmi_line: Voltage::High,
             nmi_edge: false,
+            operand_address: None,
+            page_crossed: false,
+            branch_taken: false,
+            bus_cycles: Vec::new(),
+            replay: None,
+            tick_state: None,
+            halt_reason: None,
+            break_hit: None,
+            reported_break: None,
+            waiting: false,
+            io_port: (variant == Variant::Mos6510).then(IoPort::new),
+            tracer: None,
+            trap_detector: None,
+            hooks: Hooks::default(),
         };
 
+        cpu.reset_io_port();
         cpu.init_registers();
 
-        return cpu;
+        cpu
     }
 
     pub fn reset(&mut self) {
+        self.reset_io_port();
+
         self.registers = Registers::new();
         self.init_registers();
 
+        self.nmi_edge = false;
+        self.tick_state = None;
+        self.halt_reason = None;
+        self.reported_break = None;
+        self.waiting = false;
+
         self.cycles = 8;
     }
 
+    /// Loads the pc from the reset vector. The reset sequence runs three
+    /// suppressed pushes, leaving sp at $FD, and sets the interrupt disable flag.
     pub fn init_registers(&mut self) {
-        self.registers.pc = self.memory.read_short(0xFFFC);
-        self.registers.sp = 0xFF;
+        self.registers.pc = self.memory.read_short(RESET_VECTOR);
+        self.registers.sp = 0xFD;
+        self.registers.flags.set(Flag::InterruptDisable, true);
     }
 
     pub fn load_executable(&mut self, bytes: &[u8], address: u16) -> Result<()> {
-        let start = self.memory.load(bytes, address)?;
+        self.memory.load(bytes, address)?;
 
-        self.set_reset_vector(address);
-
-        Ok(())
+        self.set_reset_vector(address)
     }
 
     pub fn load_executable_from_file(&mut self, file: &str, address: u16) -> Result<()> {
-        let start = self.memory.load_from_file(file, address)?;
+        self.memory.load_from_file(file, address)?;
 
-        self.set_reset_vector(address);
+        self.set_reset_vector(address)
+    }
 
-        Ok(())
+    fn set_reset_vector(&mut self, address: u16) -> Result<()> {
+        // Loaded rather than written so that a write protected vector can be set up
+        self.memory.load(&address.to_le_bytes(), RESET_VECTOR)
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Enables the undocumented NMOS opcodes. While disabled they execute as
+    /// single byte NOPs. The 65C02 has no undocumented opcodes.
+    pub fn set_undocumented_opcodes(&mut self, enabled: bool) {
+        self.undocumented_opcodes = enabled;
+    }
+
+    /// Makes opcodes that the chip does not define fail the step with
+    /// `StepError::InvalidOpcode` instead of executing as NOPs.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    // The tables fill undefined opcodes with NOPs. With the undocumented
+    // opcodes enabled the NMOS NOPs are real instructions.
+    fn is_invalid_opcode(&self, instruction: &Instruction<M>) -> bool {
+        let undocumented = self.undocumented_opcodes && self.variant != Variant::Cmos65C02;
+
+        matches!(instruction.instruction_type, InstructionType::NOP)
+            && instruction.opcode != 0xEA
+            && !undocumented
+    }
+
+    fn instructions(&self) -> &'static [Instruction<M>] {
+        InstructionTable::for_variant(self.variant, self.undocumented_opcodes)
+    }
+
+    /// The I/O port of a 6510.
+    pub fn io_port(&self) -> Option<IoPort> {
+        self.io_port
+    }
+
+    /// Applies levels to the pins of the I/O port that are configured as
+    /// inputs. Has no effect on variants without an I/O port.
+    pub fn set_io_port_input(&mut self, levels: u8) {
+        self.update_io_port(|port| port.input = levels);
+    }
+
+    // Reset turns all pins into inputs. The memory is notified either way, so
+    // that it starts out in the configuration of the pins.
+    fn reset_io_port(&mut self) {
+        if let Some(port) = &mut self.io_port {
+            port.direction = 0x00;
+            port.data = 0x00;
+
+            self.memory.io_port_changed(port.pins());
+        }
+    }
+
+    // Lets the memory know when the pins of the port change
+    fn update_io_port(&mut self, fun: impl FnOnce(&mut IoPort)) {
+        let Some(port) = &mut self.io_port else {
+            return;
+        };
+
+        let pins = port.pins();
+        fun(port);
+
+        if port.pins() != pins {
+            self.memory.io_port_changed(port.pins());
+        }
+    }
+
+    // The 6510 port answers reads of $0000 and $0001. Writes also reach the
+    // memory underneath.
+    fn bus_read(&mut self, address: u16) -> u8 {
+        match (self.io_port, address) {
+            (Some(port), 0x0000) => port.direction,
+            (Some(port), 0x0001) => port.pins(),
+            _ => self.memory.read_byte(address),
+        }
+    }
+
+    fn bus_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000 => self.update_io_port(|port| port.direction = value),
+            0x0001 => self.update_io_port(|port| port.data = value),
+            _ => {}
+        }
+
+        self.memory.write_byte(address, value);
+    }
+
+    pub fn is_jammed(&self) -> bool {
+        matches!(self.halt_reason, Some(HaltReason::Jammed { .. }))
+    }
+
+    /// Whether a 65C02 is waiting for an interrupt after WAI.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason
+    }
+
+    /// Bus accesses made by the last completed step.
+    pub fn bus_cycles(&self) -> &[BusCycle] {
+        &self.bus_cycles
+    }
+
+    // All accesses of the cpu go through read and write, so that they can be
+    // recorded and replayed when ticking through an instruction.
+    fn read(&mut self, address: u16) -> u8 {
+        let index = self.bus_cycles.len();
+
+        let value = match &self.replay {
+            None => self.bus_read(address),
+            Some(performed) if index < performed.len() => {
+                debug_assert_eq!(performed[index].address, address);
+                performed[index].value
+            }
+            Some(performed) if index == performed.len() => self.bus_read(address),
+            // Cycles past the current tick are not performed yet
+            Some(_) => 0,
+        };
+
+        self.bus_cycles.push(BusCycle {
+            address,
+            value,
+            direction: BusDirection::Read,
+        });
+
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let index = self.bus_cycles.len();
+        let cycle = BusCycle {
+            address,
+            value,
+            direction: BusDirection::Write,
+        };
+
+        match &self.replay {
+            None => self.bus_write(address, value),
+            Some(performed) if index == performed.len() => self.bus_write(address, value),
+            Some(_) => {}
+        }
+
+        self.bus_cycles.push(cycle);
+    }
+
+    fn fetch_byte(&mut self) -> u8 {
+        let value = self.read(self.registers.pc);
+        self.registers.pc = self.registers.pc.wrapping_add(1);
+
+        value
     }
 
-    fn set_reset_vector(&mut self, address: u16) {
-        self.memory.write_short(0xFFFC, address);
+    fn fetch_short(&mut self) -> u16 {
+        u16::from_le_bytes([self.fetch_byte(), self.fetch_byte()])
     }
 
-    fn read_current_byte(&self) -> u8 {
-        self.memory.read_byte(self.registers.pc)
+    // Pointers are read without carrying into the high byte, which wraps zero
+    // page pointers and causes the JMP ($xxFF) bug.
+    fn read_pointer(&mut self, address: u16) -> u16 {
+        let high_address = (address & 0xFF00) | (address.wrapping_add(1) & 0x00FF);
+
+        u16::from_le_bytes([self.read(address), self.read(high_address)])
     }
 
-    fn read_current_short(&self) -> u16 {
-        self.memory.read_short(self.registers.pc)
+    fn read_short(&mut self, address: u16) -> u16 {
+        u16::from_le_bytes([self.read(address), self.read(address.wrapping_add(1))])
     }
 
-    fn indexed_zero_page(&self, register: u8) -> u16 {
-        let zero_page_address = self.memory.read_byte(self.registers.pc);
+    fn indexed(&mut self, base: u16, index: u8, extra_cycle: bool) -> u16 {
+        let address = base.wrapping_add(index as u16);
+        self.page_crossed = (base & 0xFF00) != (address & 0xFF00);
 
-        zero_page_address.wrapping_add(register) as u16
+        // The high byte is fixed up a cycle later, which costs reads an extra
+        // cycle when crossing a page. Writes always spend that cycle.
+        if self.page_crossed || !extra_cycle {
+            self.read((base & 0xFF00) | (address & 0x00FF));
+        }
+
+        address
     }
 
-    fn get_operand_address(&self) -> Option<u16> {
-        match self.current_instruction?.mode {
-            AddressingMode::Absolute => Some(self.read_current_short()),
+    /// Reads the operand bytes of the current instruction, advancing the pc
+    /// past them, and resolves the address the instruction operates on.
+    fn decode_operand_address(&mut self, instruction: &Instruction<M>) -> Option<u16> {
+        self.page_crossed = false;
+
+        let address = match instruction.mode {
+            AddressingMode::Implied | AddressingMode::Accumulator => {
+                // The single cycle NOPs of the 65C02 do not read the next byte
+                if instruction.cycles > 1 {
+                    self.read(self.registers.pc);
+                }
+
+                return None;
+            }
+            AddressingMode::Immediate => {
+                let address = self.registers.pc;
+                self.registers.pc = self.registers.pc.wrapping_add(1);
+                address
+            }
+            AddressingMode::Absolute => self.fetch_short(),
             AddressingMode::AbsoluteX => {
-                Some(self.read_current_short() + (self.registers.x as u16))
+                let base = self.fetch_short();
+                self.indexed(base, self.registers.x, instruction.extra_cycle)
             }
             AddressingMode::AbsoluteY => {
-                Some(self.read_current_short() + (self.registers.y as u16))
+                let base = self.fetch_short();
+                self.indexed(base, self.registers.y, instruction.extra_cycle)
+            }
+            AddressingMode::ZeroPage => self.fetch_byte() as u16,
+            AddressingMode::ZeroPageX => {
+                let base = self.fetch_byte();
+                self.read(base as u16);
+                base.wrapping_add(self.registers.x) as u16
+            }
+            AddressingMode::ZeroPageY => {
+                let base = self.fetch_byte();
+                self.read(base as u16);
+                base.wrapping_add(self.registers.y) as u16
             }
-            AddressingMode::ZeroPage => Some(self.read_current_byte() as u16),
-            AddressingMode::ZeroPageX => Some(self.indexed_zero_page(self.registers.x)),
-            AddressingMode::ZeroPageY => Some(self.indexed_zero_page(self.registers.y)),
             AddressingMode::Indirect => {
-                let direct_address = self.memory.read_short(self.registers.pc);
-                let indirect_address = self.memory.read_short(direct_address);
-                Some(indirect_address)
+                let pointer = self.fetch_short();
+
+                match self.variant {
+                    Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => {
+                        self.read_pointer(pointer)
+                    }
+                    // The 65C02 fixes the page wrap at the cost of a cycle
+                    Variant::Cmos65C02 => {
+                        self.read(self.registers.pc.wrapping_sub(1));
+                        self.read_short(pointer)
+                    }
+                }
             }
             AddressingMode::IndirectX => {
-                let direct_address: u16 = self.read_current_short();
-                let indirect_address = direct_address + self.registers.x as u16;
-                Some(indirect_address)
+                let base = self.fetch_byte();
+                self.read(base as u16);
+                self.read_pointer(base.wrapping_add(self.registers.x) as u16)
             }
             AddressingMode::IndirectY => {
-                let direct_address: u16 = self.read_current_short();
-                let indirect_address = direct_address + self.registers.y as u16;
-                Some(indirect_address)
+                let pointer = self.fetch_byte();
+                let base = self.read_pointer(pointer as u16);
+                self.indexed(base, self.registers.y, instruction.extra_cycle)
             }
+            AddressingMode::ZeroPageIndirect => {
+                let pointer = self.fetch_byte();
+                self.read_pointer(pointer as u16)
+            }
+            AddressingMode::AbsoluteIndexedIndirect => {
+                let base = self.fetch_short();
+                self.read(self.registers.pc.wrapping_sub(1));
+                self.read_short(base.wrapping_add(self.registers.x as u16))
+            }
+            // The branch offset is fetched by the instruction after the zp operand is read
+            AddressingMode::ZeroPageRelative => self.fetch_byte() as u16,
             AddressingMode::Relative => {
-                let offset: i8 = i8::from_twos_complement_bits(self.read_current_byte());
+                let offset: i8 = i8::from_twos_complement_bits(self.fetch_byte());
+                let pc = self.registers.pc;
+                let target = pc.wrapping_add_signed(offset.into());
 
-                Some(self.registers.pc.wrapping_add_signed(offset.into()))
-            }
-            AddressingMode::Immediate | AddressingMode::Accumulator | AddressingMode::Implied => {
-                None
+                self.page_crossed = (pc & 0xFF00) != (target & 0xFF00);
+                target
             }
+        };
+
+        Some(address)
+    }
+
+    // The instruction being executed, decoded at the start of the step
+    fn instruction(&self) -> &'static Instruction<M> {
+        &self.instructions()[self.opcode as usize]
+    }
+
+    // Handlers are only used with the addressing modes that resolve an address
+    fn get_operand_address(&self) -> u16 {
+        self.operand_address.unwrap_or_default()
+    }
+
+    // Implied and accumulator instructions operate on the accumulator
+    fn get_operand_value(&mut self) -> u8 {
+        match self.operand_address {
+            Some(address) => self.read(address),
+            None => self.registers.a,
         }
     }
 
-    fn get_operand_value(&mut self) -> Option<u8> {
-        match self.current_instruction?.mode {
-            AddressingMode::Implied => None,
-            AddressingMode::Immediate => Some(self.read_current_byte()),
-            AddressingMode::Accumulator => Some(self.registers.a),
-            _ => {
-                // Previous arms prevent get_operand_address from returning None
-                let address = self.get_operand_address()?;
+    /// Base cycles of the instruction plus the penalties for crossing a page
+    /// boundary on indexed reads and for taken branches.
+    fn instruction_cycles(&self, instruction: &Instruction<M>) -> u32 {
+        let mut cycles = instruction.cycles as u32;
 
-                Some(self.memory.read_byte(address))
-            }
+        if instruction.extra_cycle && self.page_crossed {
+            cycles += 1;
         }
+
+        if self.branch_taken {
+            cycles += 1 + self.page_crossed as u32;
+        }
+
+        cycles
     }
 
+    /// The IRQ line is level sensitive, an interrupt is taken after every
+    /// instruction while it is held low and interrupts are not disabled.
     pub fn set_irq_line(&mut self, state: Voltage) {
         self.irq_line = state;
     }
 
+    /// The NMI line is edge sensitive, pulling it low latches an interrupt
+    /// that is taken after the current instruction.
+    pub fn set_nmi_line(&mut self, state: Voltage) {
+        if self.nmi_line == Voltage::High && state == Voltage::Low {
+            self.nmi_edge = true;
+        }
+
+        self.nmi_line = state;
+    }
+
     fn handle_interrupt(&mut self, typ: Interrupt) {
-        let vector_location: u16 = match typ {
-            Interrupt::Maskable => 0xFFFA,
-            Interrupt::NonMaskable => 0xFFFE,
+        self.push_short(self.registers.pc);
+
+        let flags = match typ {
+            Interrupt::Break => self.registers.flags.0 | BREAK,
+            Interrupt::Maskable | Interrupt::NonMaskable => self.registers.flags.0 & !BREAK,
         };
 
-        let isr_address = self.memory.read_short(vector_location);
+        self.push_byte(flags | UNUSED);
+        self.registers.flags.set(Flag::InterruptDisable, true);
 
-        self.push_short(self.registers.pc);
-        self.push_byte(self.registers.flags.0);
+        if self.variant == Variant::Cmos65C02 {
+            self.registers.flags.set(Flag::Decimal, false);
+        }
 
-        self.cycles += 7;
+        // An NMI that occurs before the vector is fetched hijacks BRK and IRQ.
+        // Ticks replay the vector that was fetched, later edges wait.
+        let fetched_vector = self
+            .replay
+            .as_ref()
+            .and_then(|performed| performed.get(self.bus_cycles.len()).map(|cycle| cycle.address));
+
+        let vector_location = match fetched_vector {
+            Some(address) => address,
+            None if self.nmi_edge => NMI_VECTOR,
+            None => IRQ_VECTOR,
+        };
+
+        if vector_location == NMI_VECTOR {
+            self.nmi_edge = false;
+        }
+
+        let isr_address = u16::from_le_bytes([
+            self.read(vector_location),
+            self.read(vector_location.wrapping_add(1)),
+        ]);
 
         self.registers.pc = isr_address;
     }
 
-    pub fn step(&mut self) {
-        let opcode: u8 = self.read_current_byte();
-        let current_instruction = &INSTRUCTIONS[opcode as usize];
+    fn service_interrupt(&mut self, typ: Interrupt) {
+        // The opcode fetch is discarded and the pc is read once more
+        self.read(self.registers.pc);
+        self.read(self.registers.pc);
 
-        self.current_instruction = Some(current_instruction);
+        self.handle_interrupt(typ);
+
+        self.cycles += 7;
+    }
 
-        let operand = self.get_operand_value();
+    /// Services a pending NMI or an IRQ if `interrupt_disable` is clear.
+    /// Returns whether an interrupt was taken.
+    fn poll_interrupts(&mut self, interrupt_disable: bool) -> bool {
+        if self.nmi_edge {
+            self.service_interrupt(Interrupt::NonMaskable);
+            return true;
+        }
 
-        self.registers.pc += 1;
+        if self.irq_line == Voltage::Low && !interrupt_disable {
+            self.service_interrupt(Interrupt::Maskable);
+            return true;
+        }
 
-        log::trace!(
-            "Executing instruction {:?} with opcode {:02X} ({:?}) and operand {:?}",
-            current_instruction.instruction_type,
-            current_instruction.opcode,
-            current_instruction.mode,
-            operand
-        );
+        false
+    }
 
-        if !current_instruction.is_jump() {
-            self.registers.pc += current_instruction.mode.operand_size();
+    /// Executes the next instruction. If the cpu is in the middle of an
+    /// instruction started with `tick`, the rest of that instruction is run.
+    /// A halted cpu does not advance and returns its halt reason as error.
+    pub fn step(&mut self) -> Result<StepOutcome, StepError> {
+        self.break_hit = None;
+
+        if let Some(reason) = self.halt_reason {
+            return Err(reason.into());
         }
 
-        self.execute_instruction(&current_instruction);
+        if self.tick_state.is_some() {
+            let cycles = self.cycles;
 
-        self.cycles += current_instruction.cycles as u32;
+            while self.tick_state.is_some() {
+                self.tick()?;
+            }
 
-        if self.nmi_edge == true {
-            self.handle_interrupt(Interrupt::NonMaskable);
-            return;
+            return Ok(StepOutcome::Executed {
+                opcode: self.opcode,
+                cycles: self.cycles - cycles,
+            });
+        }
+
+        self.bus_cycles.clear();
+
+        let outcome = match self.waiting {
+            true => None,
+            false => self.run_address_hook(),
+        };
+
+        let outcome = match outcome {
+            Some(outcome) => outcome,
+            None => self.execute_step()?,
+        };
+
+        self.check_breakpoints();
+
+        Ok(outcome)
+    }
+
+    fn execute_step(&mut self) -> Result<StepOutcome, StepError> {
+        let cycles = self.cycles;
+
+        if self.waiting {
+            if !self.nmi_edge && self.irq_line == Voltage::High {
+                self.read(self.registers.pc);
+                self.cycles += 1;
+
+                return Ok(StepOutcome::Waiting);
+            }
+
+            // A masked IRQ ends the wait without being serviced
+            self.waiting = false;
+
+            if self.poll_interrupts(self.registers.flags.get(Flag::InterruptDisable)) {
+                return Ok(StepOutcome::Interrupted {
+                    cycles: self.cycles - cycles,
+                });
+            }
+        }
+
+        // Ticks run the instruction again from its start, only the first one traces
+        if self.replay.as_ref().is_none_or(Vec::is_empty) {
+            self.trace();
+        }
+
+        let address = self.registers.pc;
+        let opcode: u8 = self.fetch_byte();
+
+        if self.replay.is_none() {
+            if let Some(outcome) = self.run_opcode_hook(opcode) {
+                return Ok(outcome);
+            }
+        }
+
+        let current_instruction = &self.instructions()[opcode as usize];
+
+        // The opcode is left in place, so stepping again fails the same way
+        if self.strict && self.is_invalid_opcode(current_instruction) {
+            self.registers.pc = address;
+
+            return Err(StepError::InvalidOpcode { opcode, address });
+        }
+
+        self.opcode = opcode;
+        self.current_instruction = Some(current_instruction);
+        self.branch_taken = false;
+
+        // JSR fetches the high byte of its target after pushing the return address
+        self.operand_address = match current_instruction.instruction_type {
+            InstructionType::JSR => None,
+            _ => self.decode_operand_address(current_instruction),
+        };
+
+        let interrupt_disable = self.registers.flags.get(Flag::InterruptDisable);
+
+        self.execute_instruction(current_instruction);
+
+        self.cycles += self.instruction_cycles(current_instruction);
+
+        if let Some(error) = self.memory.take_error() {
+            return Err(error.into());
+        }
+
+        if let Some(reason) = self.halt_reason {
+            return Err(reason.into());
         }
 
-        if self.irq_line == Voltage::Low
-            && self.registers.flags.get(Flag::InterruptDisable) == false
-        {
-            self.handle_interrupt(Interrupt::Maskable);
+        // The interrupt poll happens before CLI, SEI and PLP change the
+        // flag, so their effect shows an instruction later. RTI is not delayed.
+        let interrupt_disable = match current_instruction.instruction_type {
+            InstructionType::CLI | InstructionType::SEI | InstructionType::PLP => interrupt_disable,
+            _ => self.registers.flags.get(Flag::InterruptDisable),
+        };
+
+        // WAI leaves interrupts to the next step
+        if !self.waiting {
+            self.poll_interrupts(interrupt_disable);
         }
+
+        Ok(StepOutcome::Executed {
+            opcode,
+            cycles: self.cycles - cycles,
+        })
     }
 
-    fn execute_instruction(&mut self, instruction: &Instruction) {
+    fn execute_instruction(&mut self, instruction: &Instruction<M>) {
         (instruction.fun)(self);
     }
 
+    // The stack pointer wraps around within page 1 like on the real chip
     fn push_byte(&mut self, value: u8) {
-        if self.registers.sp == 0 {
-            panic!("Stack overflow");
-        }
+        self.write(self.registers.sp as u16 + 0x100, value);
 
-        self.registers.sp -= 1;
-
-        self.memory.write_byte(self.registers.sp as u16 + 0x100, value);
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
     }
 
     fn push_short(&mut self, value: u16) {
-        if self.registers.sp == 0 {
-            panic!("Stack overflow");
-        }
+        let [low, high] = value.to_le_bytes();
 
-        self.registers.sp -= 2;
-
-        self.memory.write_short(self.registers.sp as u16 + 0x100, value);
+        self.push_byte(high);
+        self.push_byte(low);
     }
 
     fn pop_byte(&mut self) -> u8 {
-        if self.registers.sp == 0xFF {
-            panic!("Stack underflow");
-        }
+        self.registers.sp = self.registers.sp.wrapping_add(1);
 
-        let value = self.memory.read_byte(self.registers.sp as u16 + 0x100);
-        self.registers.sp += 1;
-
-        value
+        self.read(self.registers.sp as u16 + 0x100)
     }
 
     fn pop_short(&mut self) -> u16 {
-        if self.registers.sp >= 0xFE {
-            panic!("Stack underflow");
-        }
+        u16::from_le_bytes([self.pop_byte(), self.pop_byte()])
+    }
 
-        let value = self.memory.read_short(self.registers.sp as u16 + 0x100);
-        self.registers.sp += 2;
+    fn pull_flags(&mut self) {
+        let unused = self.registers.flags.0 & (BREAK | UNUSED);
 
-        value
+        self.registers.flags = Flags((self.pop_byte() & !(BREAK | UNUSED)) | unused);
+    }
+
+    // Pulling instructions read the current stack slot before incrementing sp
+    fn read_stack(&mut self) {
+        self.read(self.registers.sp as u16 + 0x100);
     }
 
     fn branch_if(&mut self, condition: bool) {
-        let new_pc = self.get_operand_address().expect("PC offset should be valid");
+        let new_pc = self.get_operand_address();
 
         if condition {
+            let pc = self.registers.pc;
+
+            self.read(pc);
+
+            if self.page_crossed {
+                self.read((pc & 0xFF00) | (new_pc & 0x00FF));
+            }
+
             self.registers.pc = new_pc;
+            self.branch_taken = true;
         }
     }
 
+    // Decimal mode follows Bruce Clark's description of the NMOS and CMOS parts.
+    // The accumulator is valid for valid BCD operands only.
     fn add_bcd(&mut self, value: u8) {
-        let vh = u16::from(value >> 4);
-        let vl = u16::from(value & 0xF);
-
-        let al = u16::from(self.registers.a & 0xF);
-        let ah = u16::from(self.registers.a >> 4);
+        let a = self.registers.a as i16;
+        let b = value as i16;
+        let carry = self.registers.flags.get(Flag::Carry) as i16;
 
-        let carry = self.registers.flags.get(Flag::Carry) as u16;
+        let mut low = (a & 0x0F) + (b & 0x0F) + carry;
 
-        let mut sum_l: u16 = al + vl + carry;
-        let mut sum_h: u16 = ah + vh;
-
-        let mut carry_out = false;
-
-        if sum_l >= 0xA {
-            sum_l = (sum_l + 6) & 0xF;
-            sum_h += 1;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
         }
 
-        if sum_h >= 0xA {
-            sum_h = (sum_h + 6) & 0xF;
-            carry_out = true;
+        // N and V are taken before the high nibble is adjusted
+        let signed = (a as u8 as i8 as i16 & !0x0F) + (b as u8 as i8 as i16 & !0x0F) + low;
+        let mut sum = (a & 0xF0) + (b & 0xF0) + low;
+
+        if sum >= 0xA0 {
+            sum += 0x60;
         }
 
-        let sum: u8 = ((sum_h as u8) << 4) | (sum_l as u8);
-        let sum_binary: u8 = (u16::from(self.registers.a) + u16::from(value) + carry) as u8;
+        let binary = (a + b + carry) as u8;
 
-        let did_overflow = ((value ^ sum_binary) & (self.registers.a ^ sum_binary) & 0x80) != 0;
+        self.registers.a = sum as u8;
 
-        self.registers.a = sum;
+        self.registers.flags.set(Flag::Carry, sum >= 0x100);
+        self.registers.flags.set(Flag::Overflow, !(-128..=127).contains(&signed));
 
-        self.registers.flags.set(Flag::Carry, carry_out);
-        self.registers.flags.set(Flag::Overflow, did_overflow);
+        match self.variant {
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => {
+                self.registers.flags.set(Flag::Negative, signed & 0x80 != 0);
+                self.update_zero_flag(binary);
+            }
+            Variant::Cmos65C02 => {
+                self.update_negative_flag(self.registers.a);
+                self.update_zero_flag(self.registers.a);
+            }
+        }
     }
 
     fn add_binary(&mut self, value: u8) {
@@ -341,79 +916,166 @@ impl Cpu {
 
         self.registers.flags.set(Flag::Carry, carry_out);
         self.registers.flags.set(Flag::Overflow, did_overflow);
+        self.update_negative_flag(self.registers.a);
+        self.update_zero_flag(self.registers.a);
     }
 
+    // The flags of a decimal subtraction are those of the binary one, except
+    // for N and Z on the 65C02
     fn sbc_bcd(&mut self, value: u8) {
-        let vl = u8::from(value & 0xF);
-        let vh = u8::from(value >> 4);
+        let a = self.registers.a as i16;
+        let b = value as i16;
+        let borrow = 1 - self.registers.flags.get(Flag::Carry) as i16;
+
+        self.sbc_binary(value);
+
+        let low = (a & 0x0F) - (b & 0x0F) - borrow;
+
+        let difference = match self.variant {
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => {
+                let low = match low < 0 {
+                    true => ((low - 0x06) & 0x0F) - 0x10,
+                    false => low,
+                };
+                let difference = (a & 0xF0) - (b & 0xF0) + low;
+
+                match difference < 0 {
+                    true => difference - 0x60,
+                    false => difference,
+                }
+            }
+            Variant::Cmos65C02 => {
+                let mut difference = a - b - borrow;
 
-        let al = u8::from(self.registers.a & 0xF);
-        let ah = u8::from(self.registers.a >> 4);
+                if difference < 0 {
+                    difference -= 0x60;
+                }
 
-        let carry = 1 - u8::from(self.registers.flags.get(Flag::Carry));
-        let mut carry_out = true;
+                if low < 0 {
+                    difference -= 0x06;
+                }
 
-        let mut sum_l = al.wrapping_sub(vl).wrapping_sub(carry) & 0xF;
-        let mut sum_h = ah.wrapping_sub(vh) & 0xF;
+                difference
+            }
+        };
 
-        if sum_l > 0xA {
-            sum_l -= 6;
-            sum_h = sum_h.wrapping_sub(1) & 0xF;
-        }
+        self.registers.a = difference as u8;
 
-        if sum_h > 0xA {
-            sum_h -= 6;
-            carry_out = false;
+        if self.variant == Variant::Cmos65C02 {
+            self.update_negative_flag(self.registers.a);
+            self.update_zero_flag(self.registers.a);
         }
+    }
 
-        let sum: u8 = (sum_h << 4) | sum_l;
-        let sum_binary = self.registers.a.wrapping_sub(value).wrapping_sub(carry);
+    fn sbc_binary(&mut self, value: u8) {
+        self.add_binary(!value);
+    }
 
-        let did_overflow = ((value ^ sum_binary) & (self.registers.a ^ sum_binary) & 0x80) != 0;
+    /// Whether ADC and SBC work on BCD. The 2A03 keeps the D flag but ignores it.
+    fn decimal_mode(&self) -> bool {
+        self.registers.flags.get(Flag::Decimal) && self.variant != Variant::Ricoh2A03
+    }
 
-        self.registers.a = sum;
+    fn add_with_carry(&mut self, value: u8) {
+        if self.decimal_mode() {
+            self.add_bcd(value);
+            self.decimal_cycle();
+        } else {
+            self.add_binary(value);
+        }
+    }
 
-        self.registers.flags.set(Flag::Carry, carry_out);
-        self.registers.flags.set(Flag::Overflow, did_overflow);
+    fn subtract_with_carry(&mut self, value: u8) {
+        if self.decimal_mode() {
+            self.sbc_bcd(value);
+            self.decimal_cycle();
+        } else {
+            self.sbc_binary(value);
+        }
     }
 
-    fn sbc_binary(&mut self, value: u8) {
-        self.add_binary(!value);
+    // The 65C02 spends an extra cycle on producing valid flags in decimal mode
+    fn decimal_cycle(&mut self) {
+        if self.variant == Variant::Cmos65C02 {
+            self.read(self.registers.pc);
+            self.cycles += 1;
+        }
     }
 
-    fn replace_accumulator_or_memory_with_carry(&mut self, fun: impl Fn(u8, u8) -> (u8, bool)) {
-        let value = self.get_operand_value().expect("Could not get operand value");
+    /// Applies `fun` to the accumulator or the operand in memory, returning the
+    /// new value.
+    fn replace_accumulator_or_memory_with_carry(
+        &mut self,
+        fun: impl Fn(u8, u8) -> (u8, bool),
+    ) -> u8 {
+        let value = self.get_operand_value();
         let carry_in = self.registers.flags.get(Flag::Carry) as u8;
 
         let (value, carry) = fun(value, carry_in);
 
-        if self.current_instruction.unwrap().mode == AddressingMode::Accumulator {
+        if self.instruction().mode == AddressingMode::Accumulator {
             self.registers.a = value;
         } else {
-            let address = self.get_operand_address().unwrap();
-            self.memory.write_byte(address, value);
+            self.write_modified(value);
         }
 
         self.registers.flags.set(Flag::Carry, carry);
         self.update_negative_flag(value);
         self.update_zero_flag(value);
-    }
 
-    fn compare_register_with_memory(&mut self, register: u8) {
-        let a_before = self.registers.a;
+        value
+    }
 
-        // Subtraction in cmp should always happen without carry
-        self.registers.flags.set(Flag::Carry, true);
+    /// Like `replace_accumulator_or_memory_with_carry` without touching any flags.
+    fn replace_accumulator_or_memory(&mut self, fun: impl Fn(u8) -> u8) -> u8 {
+        let value = fun(self.get_operand_value());
 
-        self.registers.a = register;
-        self.sbc();
+        if self.instruction().mode == AddressingMode::Accumulator {
+            self.registers.a = value;
+        } else {
+            self.write_modified(value);
+        }
 
-        self.registers.a = a_before;
+        value
     }
 
-    fn update_zero_flag(&mut self, value: u8) {
-        self.registers.flags.set(Flag::Zero, value == 0);
-    }
+    /// Writes the result of a read-modify-write instruction. The NMOS 6502
+    /// writes the unmodified value back first, the 65C02 reads it again.
+    fn write_modified(&mut self, value: u8) {
+        let address = self.get_operand_address();
+
+        match self.variant {
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => {
+                // The operand was read by the access right before
+                let last_read = self.bus_cycles.last().map_or(0, |cycle| cycle.value);
+                self.write(address, last_read);
+            }
+            Variant::Cmos65C02 => {
+                self.read(address);
+            }
+        }
+
+        self.write(address, value);
+    }
+
+    fn compare_register_with_memory(&mut self, register: u8) {
+        let value = self.get_operand_value();
+
+        self.compare(register, value);
+    }
+
+    // Compares are binary subtractions without borrow that leave V untouched
+    fn compare(&mut self, register: u8, value: u8) {
+        let result = register.wrapping_sub(value);
+
+        self.registers.flags.set(Flag::Carry, register >= value);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn update_zero_flag(&mut self, value: u8) {
+        self.registers.flags.set(Flag::Zero, value == 0);
+    }
 
     fn update_negative_flag(&mut self, value: u8) {
         let flag = 0b10000000;
@@ -422,3 +1084,1213 @@ impl Cpu {
         self.registers.flags.set(Flag::Negative, is_negative);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use rs_6502_macros::asm6502;
+
+    use super::*;
+    use crate::bus::{Bus, Device, UnmappedReadPolicy};
+
+    // 2K of RAM mirrored across the lower half, ROM in the upper half
+    struct MirroredMemory {
+        ram: [u8; 0x800],
+        rom: [u8; 0x8000],
+    }
+
+    impl Memory for MirroredMemory {
+        fn new() -> Self {
+            Self {
+                ram: [0; 0x800],
+                rom: [0; 0x8000],
+            }
+        }
+
+        fn read_byte(&self, address: u16) -> u8 {
+            match address {
+                0x0000..=0x7FFF => self.ram[address as usize & 0x7FF],
+                _ => self.rom[address as usize - 0x8000],
+            }
+        }
+
+        fn read_short(&self, address: u16) -> u16 {
+            u16::from_le_bytes([
+                self.read_byte(address),
+                self.read_byte(address.wrapping_add(1)),
+            ])
+        }
+
+        fn write_byte(&mut self, address: u16, value: u8) {
+            if address < 0x8000 {
+                self.ram[address as usize & 0x7FF] = value;
+            }
+        }
+
+        fn write_short(&mut self, address: u16, value: u16) {
+            self.write_byte(address, value as u8);
+            self.write_byte(address.wrapping_add(1), (value >> 8) as u8);
+        }
+
+        fn load_from_file(&mut self, name: &str, address: u16) -> Result<()> {
+            self.load(&std::fs::read(name)?, address)
+        }
+
+        fn load(&mut self, executable: &[u8], address: u16) -> Result<()> {
+            let start = address as usize - 0x8000;
+
+            self.rom[start..start + executable.len()].copy_from_slice(executable);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_memory() {
+        let mut memory = MirroredMemory::new();
+
+        // INX, INX, TXA
+        memory.load(&[0xE8, 0xE8, 0x8A], 0x8000).unwrap();
+        memory.load(&[0x00, 0x80], 0xFFFC).unwrap();
+
+        let mut cpu = Cpu::with_memory(memory);
+
+        assert_eq!(cpu.registers.pc, 0x8000);
+
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(cpu.registers.pc, 0x8003);
+        assert_eq!(cpu.registers.a, 2);
+    }
+
+    fn cpu_with_program(program: &[u8], address: u16) -> Cpu {
+        cpu_with_variant(Variant::Nmos6502, program, address)
+    }
+
+    fn cpu_with_variant(variant: Variant, program: &[u8], address: u16) -> Cpu {
+        let mut cpu = Cpu::with_variant(DefaultMemory::new(), variant);
+
+        cpu.load_executable(program, address).unwrap();
+        cpu.init_registers();
+
+        cpu
+    }
+
+    fn step_cycles(cpu: &mut Cpu) -> u32 {
+        let before = cpu.cycles;
+        cpu.step().unwrap();
+
+        cpu.cycles - before
+    }
+
+    #[test]
+    fn test_base_cycles() {
+        // LDA #$01, STA $0300, INC $0300, JSR $0210, NOP, ..., RTS
+        let mut program = vec![0xA9, 0x01, 0x8D, 0x00, 0x03, 0xEE, 0x00, 0x03];
+        program.extend([0x20, 0x10, 0x02, 0xEA]);
+        program.resize(0x10, 0xEA);
+        program.push(0x60);
+
+        let mut cpu = cpu_with_program(&program, 0x0200);
+
+        let cycles: Vec<u32> = (0..6).map(|_| step_cycles(&mut cpu)).collect();
+
+        assert_eq!(cycles, [2, 4, 6, 6, 6, 2]);
+        assert_eq!(cpu.memory.read_byte(0x0300), 2);
+        assert_eq!(cpu.registers.pc, 0x020C);
+    }
+
+    #[test]
+    fn test_page_crossing_cycles() {
+        // LDX #$20, LDA $02F0,X, LDA $0200,X, STA $02F0,X, LDY #$20, LDA ($10),Y
+        let program = [
+            0xA2, 0x20, 0xBD, 0xF0, 0x02, 0xBD, 0x00, 0x02, 0x9D, 0xF0, 0x02, 0xA0, 0x20, 0xB1,
+            0x10,
+        ];
+
+        let mut cpu = cpu_with_program(&program, 0x0200);
+        cpu.memory.write_short(0x0010, 0x12F0);
+
+        let cycles: Vec<u32> = (0..6).map(|_| step_cycles(&mut cpu)).collect();
+
+        assert_eq!(cycles, [2, 5, 4, 5, 2, 6]);
+    }
+
+    #[test]
+    fn test_branch_cycles() {
+        // SEC, BCC +0 (not taken), BCS +0 (taken), BCS -> next page (taken)
+        let mut cpu = cpu_with_program(&[0x38, 0x90, 0x00, 0xB0, 0x00, 0xB0, 0x7F], 0x02F0);
+
+        let cycles: Vec<u32> = (0..4).map(|_| step_cycles(&mut cpu)).collect();
+
+        assert_eq!(cycles, [2, 2, 3, 4]);
+        assert_eq!(cpu.registers.pc, 0x02F7 + 0x7F);
+    }
+
+    fn cpu_with_handlers(program: &[u8]) -> Cpu {
+        // RTI at both handlers
+        let mut cpu = cpu_with_program(program, 0x0200);
+        cpu.memory.write_byte(0x0400, 0x40);
+        cpu.memory.write_byte(0x0500, 0x40);
+        cpu.memory.write_short(NMI_VECTOR, 0x0400);
+        cpu.memory.write_short(IRQ_VECTOR, 0x0500);
+
+        cpu
+    }
+
+    #[test]
+    fn test_irq() {
+        // CLI, NOP, SEI, NOP
+        let mut cpu = cpu_with_handlers(&[0x58, 0xEA, 0x78, 0xEA]);
+        assert_eq!(cpu.registers.sp, 0xFD);
+
+        cpu.set_irq_line(Voltage::Low);
+        cpu.step().unwrap();
+
+        // CLI takes effect after the next instruction
+        assert_eq!(cpu.registers.pc, 0x0201);
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.registers.pc, 0x0500);
+        assert_eq!(cpu.cycles, 2 + 2 + 7);
+        assert!(cpu.registers.flags.get(Flag::InterruptDisable));
+        assert_eq!(cpu.memory.read_short(0x01FC), 0x0202);
+        assert_eq!(cpu.memory.read_byte(0x01FB), 0b0010_0000);
+
+        // Returning with the line still low takes the interrupt again
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0500);
+        assert_eq!(cpu.registers.sp, 0xFA);
+
+        cpu.set_irq_line(Voltage::High);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0202);
+        assert!(!cpu.registers.flags.get(Flag::InterruptDisable));
+        assert!(!cpu.registers.flags.get(Flag::Break));
+
+        // SEI is delayed as well, the interrupt after it sees the flag set
+        cpu.set_irq_line(Voltage::Low);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0500);
+        assert_eq!(cpu.memory.read_short(0x01FC), 0x0203);
+        assert_eq!(cpu.memory.read_byte(0x01FB), 0b0010_0100);
+
+        // Masked interrupts are ignored
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0204);
+    }
+
+    #[test]
+    fn test_nmi_edge() {
+        let mut cpu = cpu_with_handlers(&[0xEA; 4]);
+
+        cpu.set_nmi_line(Voltage::Low);
+        cpu.step().unwrap();
+
+        // NMIs are taken even with interrupts disabled
+        assert_eq!(cpu.registers.pc, 0x0400);
+        assert_eq!(cpu.memory.read_byte(0x01FB), 0b0010_0100);
+
+        // Holding the line low does not trigger another NMI
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0202);
+
+        cpu.set_nmi_line(Voltage::High);
+        cpu.set_nmi_line(Voltage::Low);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0400);
+    }
+
+    #[test]
+    fn test_brk() {
+        // CLI, BRK, padding, NOP
+        let mut cpu = cpu_with_handlers(&[0x58, 0x00, 0xFF, 0xEA]);
+
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(cpu.registers.pc, 0x0500);
+        assert_eq!(cpu.cycles, 2 + 7);
+        assert!(cpu.registers.flags.get(Flag::InterruptDisable));
+        assert_eq!(cpu.memory.read_short(0x01FC), 0x0203);
+        assert_eq!(cpu.memory.read_byte(0x01FB), 0b0011_0000);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0203);
+        assert_eq!(cpu.registers.flags.0, 0b0010_0000);
+    }
+
+    #[test]
+    fn test_nmi_hijacks_brk() {
+        let mut cpu = cpu_with_handlers(&[0x00, 0xFF, 0xEA]);
+
+        cpu.set_nmi_line(Voltage::Low);
+        cpu.step().unwrap();
+
+        // The NMI vector is used but the pushed flags still show a BRK
+        assert_eq!(cpu.registers.pc, 0x0400);
+        assert_eq!(cpu.cycles, 7);
+        assert_eq!(cpu.memory.read_byte(0x01FB), 0b0011_0100);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0202);
+    }
+
+    #[test]
+    fn test_nmi_hijack_while_ticking() {
+        // The edge arrives before the vector fetch, BRK is hijacked
+        let mut cpu = cpu_with_handlers(&[0x00, 0xFF, 0xEA]);
+
+        for _ in 0..3 {
+            cpu.tick().unwrap();
+        }
+
+        cpu.set_nmi_line(Voltage::Low);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0400);
+        assert_eq!(cpu.registers.sp, 0xFA);
+
+        // The edge arrives after the low byte of the IRQ vector was read, the
+        // NMI is taken after BRK
+        let mut cpu = cpu_with_handlers(&[0x00, 0xFF, 0xEA]);
+
+        for _ in 0..6 {
+            cpu.tick().unwrap();
+        }
+
+        cpu.set_nmi_line(Voltage::Low);
+        cpu.tick().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0400);
+        assert_eq!(cpu.registers.sp, 0xF7);
+        assert_eq!(cpu.memory.read_short(0x01F9), 0x0500);
+    }
+
+    #[test]
+    fn test_one_bus_access_per_cycle() {
+        let setups = [
+            (Variant::Nmos6502, false),
+            (Variant::Nmos6502, true),
+            (Variant::Ricoh2A03, true),
+            (Variant::Mos6510, false),
+            (Variant::Cmos65C02, false),
+            (Variant::Cmos65C02, true),
+        ];
+
+        for (variant, flag) in setups {
+            for opcode in 0..=0xFF {
+                for index in [0x00, 0xFF] {
+                    let mut cpu = cpu_with_variant(variant, &[opcode, 0xF0, 0x02], 0x0200);
+                    cpu.set_undocumented_opcodes(flag);
+                    cpu.registers.flags.set(Flag::Decimal, flag);
+                    cpu.registers.sp = 0xF0;
+                    cpu.registers.x = index;
+                    cpu.registers.y = index;
+
+                    // KIL and STP halt the cpu after their cycles have run
+                    if let Err(error) = cpu.step() {
+                        assert!(cpu.halt_reason().is_some(), "{error}");
+                    }
+
+                    assert_eq!(
+                        cpu.bus_cycles().len() as u32,
+                        cpu.cycles,
+                        "opcode {opcode:02X}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmos_instructions() {
+        // LDA #$F0, STZ $10, TSB $10, SMB0 $10, BBS0 $10 +2, (LDA #$00), INC A, PHA, PLY,
+        // TRB $10, BRA -2
+        let program = [
+            0xA9, 0xF0, 0x64, 0x10, 0x04, 0x10, 0x87, 0x10, 0x8F, 0x10, 0x02, 0xA9, 0x00, 0x1A,
+            0x48, 0x7A, 0x14, 0x10, 0x80, 0xFE,
+        ];
+
+        let mut cpu = cpu_with_variant(Variant::Cmos65C02, &program, 0x0200);
+        cpu.memory.write_byte(0x0010, 0xFF);
+
+        let cycles: Vec<u32> = (0..11).map(|_| step_cycles(&mut cpu)).collect();
+
+        assert_eq!(cycles, [2, 3, 5, 5, 6, 2, 3, 4, 5, 3, 3]);
+        assert_eq!(cpu.registers.y, 0xF1);
+        assert_eq!(cpu.memory.read_byte(0x0010), 0x00);
+        assert!(!cpu.registers.flags.get(Flag::Zero));
+        assert_eq!(cpu.registers.pc, 0x0212);
+    }
+
+    #[test]
+    fn test_cmos_fixes() {
+        // JMP ($02FF)
+        let mut nmos = cpu_with_program(&[0x6C, 0xFF, 0x02], 0x0200);
+        let mut cmos = cpu_with_variant(Variant::Cmos65C02, &[0x6C, 0xFF, 0x02], 0x0200);
+
+        for cpu in [&mut nmos, &mut cmos] {
+            cpu.memory.load(&[0x34, 0x12], 0x02FF).unwrap();
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(nmos.registers.pc, 0x6C34);
+        assert_eq!((cmos.registers.pc, cmos.cycles), (0x1234, 6));
+
+        // SED, CLC, LDA #$99, ADC #$01, BRK
+        let program = [0xF8, 0x18, 0xA9, 0x99, 0x69, 0x01, 0x00];
+        let mut nmos = cpu_with_program(&program, 0x0200);
+        let mut cmos = cpu_with_variant(Variant::Cmos65C02, &program, 0x0200);
+
+        for cpu in [&mut nmos, &mut cmos] {
+            for _ in 0..4 {
+                cpu.step().unwrap();
+            }
+
+            assert_eq!(cpu.registers.a, 0x00);
+            assert!(cpu.registers.flags.get(Flag::Carry));
+        }
+
+        // Only the 65C02 sets N and Z from the decimal result, taking a cycle for it
+        assert!(!nmos.registers.flags.get(Flag::Zero));
+        assert!(nmos.registers.flags.get(Flag::Negative));
+        assert!(cmos.registers.flags.get(Flag::Zero));
+        assert!(!cmos.registers.flags.get(Flag::Negative));
+        assert_eq!((nmos.cycles, cmos.cycles), (8, 9));
+
+        // The 65C02 clears D when taking an interrupt
+        nmos.step().unwrap();
+        cmos.step().unwrap();
+
+        assert!(nmos.registers.flags.get(Flag::Decimal));
+        assert!(!cmos.registers.flags.get(Flag::Decimal));
+    }
+
+    #[test]
+    fn test_2a03_ignores_decimal_flag() {
+        // SED, LDA #$09, CLC, ADC #$01, SEC, SBC #$02
+        let program = [0xF8, 0xA9, 0x09, 0x18, 0x69, 0x01, 0x38, 0xE9, 0x02];
+        let mut nmos = cpu_with_program(&program, 0x0200);
+        let mut ricoh = cpu_with_variant(Variant::Ricoh2A03, &program, 0x0200);
+
+        for cpu in [&mut nmos, &mut ricoh] {
+            for _ in 0..4 {
+                cpu.step().unwrap();
+            }
+        }
+
+        assert_eq!((nmos.registers.a, ricoh.registers.a), (0x10, 0x0A));
+        assert!(ricoh.registers.flags.get(Flag::Decimal));
+
+        for cpu in [&mut nmos, &mut ricoh] {
+            for _ in 0..2 {
+                cpu.step().unwrap();
+            }
+        }
+
+        assert_eq!((nmos.registers.a, ricoh.registers.a), (0x08, 0x08));
+    }
+
+    // RAM with a ROM at $A000-$BFFF that is banked in while port pin 0 is high
+    struct PortBankedMemory {
+        ram: DefaultMemory,
+        rom_visible: bool,
+        changes: Vec<u8>,
+    }
+
+    impl Memory for PortBankedMemory {
+        fn new() -> Self {
+            Self {
+                ram: DefaultMemory::new(),
+                rom_visible: false,
+                changes: Vec::new(),
+            }
+        }
+
+        fn read_byte(&self, address: u16) -> u8 {
+            match address {
+                0xA000..=0xBFFF if self.rom_visible => 0xEA,
+                _ => self.ram.read_byte(address),
+            }
+        }
+
+        fn read_short(&self, address: u16) -> u16 {
+            u16::from_le_bytes([
+                self.read_byte(address),
+                self.read_byte(address.wrapping_add(1)),
+            ])
+        }
+
+        fn write_byte(&mut self, address: u16, value: u8) {
+            self.ram.write_byte(address, value);
+        }
+
+        fn write_short(&mut self, address: u16, value: u16) {
+            self.ram.write_short(address, value);
+        }
+
+        fn load_from_file(&mut self, name: &str, address: u16) -> Result<()> {
+            self.ram.load_from_file(name, address)
+        }
+
+        fn load(&mut self, executable: &[u8], address: u16) -> Result<()> {
+            self.ram.load(executable, address)
+        }
+
+        fn io_port_changed(&mut self, pins: u8) {
+            self.rom_visible = pins & 1 != 0;
+            self.changes.push(pins);
+        }
+    }
+
+    #[test]
+    fn test_6510_io_port() {
+        let mut memory = PortBankedMemory::new();
+
+        // LDA #$07, STA $00, LDA #$36, STA $01, LDA $A000, LDX $01, LDY $00
+        let program = [
+            0xA9, 0x07, 0x85, 0x00, 0xA9, 0x36, 0x85, 0x01, 0xAD, 0x00, 0xA0, 0xA6, 0x01, 0xA4,
+            0x00,
+        ];
+        memory.load(&program, 0x0200).unwrap();
+        memory.load(&[0x00, 0x02], RESET_VECTOR).unwrap();
+        memory.write_byte(0xA000, 0x11);
+
+        let mut cpu = Cpu::with_variant(memory, Variant::Mos6510);
+
+        assert!(cpu.memory.rom_visible);
+        assert_eq!(cpu.io_port().unwrap().pins(), 0xFF);
+
+        for _ in 0..7 {
+            cpu.step().unwrap();
+        }
+
+        assert_eq!(cpu.memory.changes, [0xFF, 0xF8, 0xFE]);
+        assert_eq!(cpu.registers.a, 0x11);
+        assert_eq!((cpu.registers.x, cpu.registers.y), (0xFE, 0x07));
+
+        // Writes to the port also reach the memory underneath
+        assert_eq!(cpu.memory.read_byte(0x0001), 0x36);
+
+        cpu.set_io_port_input(0x01);
+        assert_eq!(cpu.io_port().unwrap().pins(), 0x06);
+        assert_eq!(cpu.memory.changes.last(), Some(&0x06));
+
+        // Reset turns all pins into inputs that follow the levels applied by the host
+        cpu.reset();
+        assert_eq!(cpu.io_port().unwrap().pins(), 0x01);
+        assert!(cpu.memory.rom_visible);
+        assert_eq!(Cpu::new().io_port(), None);
+    }
+
+    #[test]
+    fn test_wai_and_stp() {
+        // WAI, INX, STP
+        let mut cpu = cpu_with_variant(Variant::Cmos65C02, &[0xCB, 0xE8, 0xDB], 0x0200);
+        cpu.memory.write_byte(0x0500, 0x40);
+        cpu.memory.write_short(IRQ_VECTOR, 0x0500);
+
+        cpu.step().unwrap();
+        assert!(cpu.is_waiting());
+
+        for _ in 0..3 {
+            assert_eq!(step_cycles(&mut cpu), 1);
+        }
+
+        assert_eq!(cpu.registers.pc, 0x0201);
+
+        // A masked interrupt ends the wait without being serviced
+        cpu.set_irq_line(Voltage::Low);
+        cpu.step().unwrap();
+
+        assert!(!cpu.is_waiting());
+        assert_eq!(cpu.registers.x, 1);
+
+        assert!(cpu.step().is_err());
+        assert_eq!(
+            cpu.halt_reason(),
+            Some(HaltReason::Stopped { address: 0x0202 })
+        );
+        assert!(!cpu.is_jammed());
+    }
+
+    #[test]
+    fn test_wai_and_stp_while_ticking() {
+        // CLI, WAI, INX, STP
+        let mut cpu = cpu_with_variant(Variant::Cmos65C02, &[0x58, 0xCB, 0xE8, 0xDB], 0x0200);
+        cpu.memory.write_byte(0x0500, 0x40);
+        cpu.memory.write_short(IRQ_VECTOR, 0x0500);
+        cpu.step().unwrap();
+
+        // The wait starts on the last cycle of WAI
+        for _ in 0..2 {
+            cpu.tick().unwrap();
+            assert!(!cpu.is_waiting());
+        }
+
+        cpu.tick().unwrap();
+        assert!(cpu.is_waiting());
+
+        for _ in 0..3 {
+            assert_eq!(cpu.tick().unwrap().address, 0x0202);
+        }
+
+        // The interrupt keeps the cpu waiting until it has been entered
+        cpu.set_irq_line(Voltage::Low);
+
+        for _ in 0..6 {
+            cpu.tick().unwrap();
+            assert!(cpu.is_waiting());
+        }
+
+        cpu.tick().unwrap();
+        assert!(!cpu.is_waiting());
+        assert_eq!(cpu.registers.pc, 0x0500);
+
+        cpu.set_irq_line(Voltage::High);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.x, 1);
+
+        let reason = HaltReason::Stopped { address: 0x0203 };
+
+        for _ in 0..2 {
+            cpu.tick().unwrap();
+            assert_eq!(cpu.halt_reason(), None);
+        }
+
+        assert_eq!(cpu.tick(), Err(StepError::Halted(reason)));
+        assert_eq!(cpu.halt_reason(), Some(reason));
+        assert_eq!(cpu.bus_cycles().len(), 3);
+    }
+
+    #[test]
+    fn test_kil_jams() {
+        // INX, KIL
+        let mut cpu = cpu_with_handlers(&[0xE8, 0x02, 0xE8]);
+
+        cpu.step().unwrap();
+        assert!(!cpu.is_jammed());
+
+        let error = cpu.step().unwrap_err();
+        let reason = HaltReason::Jammed {
+            opcode: 0x02,
+            address: 0x0201,
+        };
+
+        assert_eq!(error, StepError::Halted(reason));
+        assert_eq!(cpu.halt_reason(), Some(reason));
+
+        // Neither steps nor interrupts get the cpu going again
+        let (pc, cycles) = (cpu.registers.pc, cpu.cycles);
+        cpu.set_nmi_line(Voltage::Low);
+
+        assert!(cpu.step().is_err());
+        assert!(cpu.tick().is_err());
+        assert_eq!((cpu.registers.pc, cpu.cycles), (pc, cycles));
+
+        cpu.reset();
+        assert!(!cpu.is_jammed());
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.x, 1);
+    }
+
+    #[test]
+    fn test_kil_while_ticking() {
+        // KIL, INX
+        let mut cpu = cpu_with_handlers(&[0x02, 0xE8]);
+        let reason = HaltReason::Jammed {
+            opcode: 0x02,
+            address: 0x0200,
+        };
+
+        let fetch = cpu.tick().unwrap();
+        assert_eq!((fetch.address, fetch.value), (0x0200, 0x02));
+        assert!(!cpu.is_jammed());
+
+        // The cpu jams on the last cycle, which is still recorded
+        assert_eq!(cpu.tick(), Err(StepError::Halted(reason)));
+        assert_eq!(cpu.halt_reason(), Some(reason));
+        assert_eq!(cpu.bus_cycles().len(), 2);
+        assert_eq!(cpu.bus_cycles()[1].address, 0x0201);
+
+        assert_eq!(cpu.tick(), Err(StepError::Halted(reason)));
+        assert_eq!(cpu.step(), Err(StepError::Halted(reason)));
+        assert_eq!(cpu.registers.x, 0);
+    }
+
+    #[test]
+    fn test_run_control() {
+        // INX, INX, INX, JMP $0200 ... KIL
+        let mut cpu = cpu_with_program(&[0xE8, 0xE8, 0xE8, 0x4C, 0x00, 0x02, 0x02], 0x0200);
+
+        assert_eq!(cpu.run_instructions(2), Ok(StopReason::BudgetExhausted));
+        assert_eq!(cpu.registers.x, 2);
+
+        // Whole instructions run until the budget is used up
+        let cycles = cpu.cycles;
+        assert_eq!(cpu.run_cycles(3), Ok(StopReason::BudgetExhausted));
+        assert_eq!(cpu.cycles - cycles, 5);
+        assert_eq!(cpu.registers.pc, 0x0200);
+
+        let breakpoint = StopReason::Breakpoint { pc: 0x0203 };
+        assert_eq!(cpu.run_until_pc(0x0203), Ok(breakpoint));
+        assert_eq!(cpu.run_until(|cpu| cpu.registers.x == 9), Ok(breakpoint));
+
+        cpu.memory.write_byte(0x0203, 0x02);
+
+        let reason = StopReason::Halted(HaltReason::Jammed {
+            opcode: 0x02,
+            address: 0x0203,
+        });
+        assert_eq!(cpu.run_until_pc(0x0000), Ok(reason));
+    }
+
+    #[test]
+    fn test_trap_detection() {
+        let program = asm6502! {
+                    .org $0200
+                    LDX #3
+            count:  DEX
+                    STX $0300
+                    BNE count
+            poll:   LDA $0300
+                    CMP #1
+                    BCC poll
+            done:   JMP done
+        };
+
+        let mut cpu = cpu_with_program(program.bytes, program.origin);
+
+        assert_eq!(cpu.run_instructions(30), Ok(StopReason::BudgetExhausted));
+
+        // The counting loop writes, the polling loop reads the same value forever
+        cpu.reset();
+        cpu.set_trap_detection(true);
+
+        let (poll, done) = (
+            program.label("poll").unwrap(),
+            program.label("done").unwrap(),
+        );
+
+        // The trap is noticed at the first state that repeats
+        let reason = cpu.run_instructions(30).unwrap();
+        assert!(matches!(reason, StopReason::Trapped { pc } if (poll..done).contains(&pc)));
+        assert_eq!(cpu.registers.x, 0);
+
+        cpu.memory.write_byte(0x0300, 1);
+
+        assert_eq!(
+            cpu.run_instructions(30),
+            Ok(StopReason::Trapped { pc: done })
+        );
+
+        // Enabled interrupts do not end the loop while none is pending
+        cpu.registers.flags.set(Flag::InterruptDisable, false);
+        assert_eq!(
+            cpu.run_instructions(30),
+            Ok(StopReason::Trapped { pc: done })
+        );
+
+        // A masked IRQ is not pending
+        cpu.registers.flags.set(Flag::InterruptDisable, true);
+        cpu.set_irq_line(Voltage::Low);
+        assert_eq!(
+            cpu.run_instructions(30),
+            Ok(StopReason::Trapped { pc: done })
+        );
+
+        // Once it is taken the handler returns into the loop, RTI
+        cpu.memory.write_byte(0x0400, 0x40);
+        cpu.memory.write_short(0xFFFE, 0x0400);
+        cpu.registers.flags.set(Flag::InterruptDisable, false);
+        assert_eq!(cpu.run_instructions(30), Ok(StopReason::BudgetExhausted));
+    }
+
+    // Counts the reads of a device that always reads as `value`
+    struct Counter {
+        value: u8,
+        reads: Rc<Cell<u32>>,
+    }
+
+    impl Device for Counter {
+        fn read(&mut self, _offset: u16) -> anyhow::Result<u8> {
+            self.reads.set(self.reads.get() + 1);
+            Ok(self.value)
+        }
+
+        fn write(&mut self, _offset: u16, _value: u8) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    // RAM in the lower half, a counter in the page at `start` and the reset
+    // vector pointing to $0200
+    fn bus_with_counter(start: u16, value: u8, reads: &Rc<Cell<u32>>) -> Bus {
+        let mut bus = Bus::new();
+
+        bus.map_ram(0x0000..=0x7FFF);
+        bus.map(
+            start..=start + 0xFF,
+            Box::new(Counter {
+                value,
+                reads: Rc::clone(reads),
+            }),
+        );
+        bus.map_rom(0xFFFC..=0xFFFD, vec![0x00, 0x02]);
+
+        bus
+    }
+
+    #[test]
+    fn test_hooks() {
+        let program = asm6502! {
+            PUTCHAR = $FFF0
+
+                    .org $0200
+                    LDX #0
+            @loop:  LDA message,X
+                    BEQ @exit
+                    JSR PUTCHAR
+                    INX
+                    BNE @loop
+            @exit:  .byte $FF, 3    // Exit with code 3
+            message: .byte "Hi", 0
+        };
+
+        let mut cpu = cpu_with_program(program.bytes, program.origin);
+        cpu.memory.write_byte(0xFFF0, 0x60);
+
+        let output = Rc::new(RefCell::new(String::new()));
+        let sink = Rc::clone(&output);
+
+        // The hook runs before the RTS at its address returns to the program
+        cpu.add_address_hook(0xFFF0, move |cpu| {
+            sink.borrow_mut().push(cpu.registers.a as char);
+            HookAction::Continue
+        });
+
+        cpu.add_opcode_hook(0xFF, |cpu| {
+            let code = cpu.memory.read_byte(cpu.registers.pc);
+            HookAction::Exit(code as i32)
+        });
+
+        assert_eq!(
+            cpu.run_instructions(100),
+            Ok(StopReason::Exited { code: 3 })
+        );
+        assert_eq!(*output.borrow(), "Hi");
+        assert_eq!(cpu.registers.x, 2);
+
+        assert!(cpu.remove_opcode_hook(0xFF));
+        assert!(!cpu.remove_opcode_hook(0xFF));
+        assert!(cpu.remove_address_hook(0xFFF0));
+    }
+
+    #[test]
+    fn test_opcode_hooks_on_fetched_opcode() {
+        // A device full of NOPs at the reset address
+        let reads = Rc::new(Cell::new(0));
+        let mut cpu = Cpu::with_memory(bus_with_counter(0x0200, 0xEA, &reads));
+        cpu.add_opcode_hook(0xFF, |_| HookAction::Continue);
+
+        // Only the opcode fetch and the dummy read of NOP reach the device
+        assert_eq!(
+            cpu.step(),
+            Ok(StepOutcome::Executed {
+                opcode: 0xEA,
+                cycles: 2
+            })
+        );
+        assert_eq!(reads.get(), 2);
+
+        // Code in a device reaches the hook through the opcode that was fetched
+        let reads = Rc::new(Cell::new(0));
+        let mut cpu = Cpu::with_memory(bus_with_counter(0x0200, 0xFF, &reads));
+        cpu.add_opcode_hook(0xFF, |_| HookAction::Continue);
+
+        assert_eq!(
+            cpu.step(),
+            Ok(StepOutcome::Executed {
+                opcode: 0xFF,
+                cycles: 1
+            })
+        );
+        assert_eq!((reads.get(), cpu.registers.pc), (1, 0x0201));
+    }
+
+    #[test]
+    fn test_hooks_removing_themselves() {
+        // NOP, NOP, JMP $0200
+        let mut cpu = cpu_with_program(&[0xEA, 0xEA, 0x4C, 0x00, 0x02], 0x0200);
+        let calls = Rc::new(Cell::new(0));
+
+        let counter = Rc::clone(&calls);
+        cpu.add_address_hook(0x0200, move |cpu| {
+            counter.set(counter.get() + 1);
+            assert!(cpu.remove_address_hook(0x0200));
+            assert!(!cpu.remove_address_hook(0x0200));
+            HookAction::Continue
+        });
+
+        // A replacement added by the hook is kept in its place
+        let counter = Rc::clone(&calls);
+        cpu.add_address_hook(0x0201, move |cpu| {
+            counter.set(counter.get() + 10);
+            cpu.add_address_hook(0x0201, |_| HookAction::Exit(1));
+            HookAction::Continue
+        });
+
+        assert_eq!(cpu.run_instructions(10), Ok(StopReason::Exited { code: 1 }));
+        assert_eq!(calls.get(), 11);
+        assert_eq!(cpu.registers.pc, 0x0201);
+
+        // Removing and adding back a hook during its call keeps the new one
+        cpu.add_opcode_hook(0xEA, |cpu| {
+            cpu.remove_opcode_hook(0xEA);
+            cpu.add_opcode_hook(0xEA, |_| HookAction::Exit(2));
+            HookAction::Continue
+        });
+        assert!(cpu.remove_address_hook(0x0201));
+
+        assert_eq!(cpu.run_instructions(10), Ok(StopReason::Exited { code: 2 }));
+        assert_eq!(cpu.registers.pc, 0x0201);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        // LDX #$10, INC $0300,X, LDA $0310, NOP, NOP
+        let program = [0xA2, 0x10, 0xFE, 0x00, 0x03, 0xAD, 0x10, 0x03, 0xEA, 0xEA];
+        let mut cpu = cpu_with_program(&program, 0x0200);
+
+        cpu.breakpoints.add_pc(0x0202);
+        cpu.breakpoints.add_instruction_type(InstructionType::NOP);
+
+        let watchpoint = cpu.breakpoints.add_watchpoint(0x0310..=0x0310, WatchKind::Write);
+
+        // The breakpoint stops before the instruction, the run resumes past it
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Breakpoint { pc: 0x0202 })
+        );
+        assert_eq!(cpu.break_hit(), Some(StopReason::Breakpoint { pc: 0x0202 }));
+
+        // The dummy write of the read-modify-write is the first one seen
+        let access = BusCycle {
+            address: 0x0310,
+            value: 0x00,
+            direction: BusDirection::Write,
+        };
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Watchpoint { pc: 0x0205, access })
+        );
+
+        assert!(cpu.breakpoints.remove_watchpoint(watchpoint));
+        cpu.breakpoints.add_watchpoint(0x0300..=0x03FF, WatchKind::Read);
+
+        let access = BusCycle {
+            address: 0x0310,
+            value: 0x01,
+            direction: BusDirection::Read,
+        };
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Watchpoint { pc: 0x0208, access })
+        );
+
+        // Hits are checked after ticking through an instruction as well
+        cpu.breakpoints.clear();
+        cpu.breakpoints.add_opcode(0xEA);
+
+        while cpu.tick().unwrap().address != 0x0209 {}
+        assert_eq!(cpu.break_hit(), Some(StopReason::Breakpoint { pc: 0x0209 }));
+    }
+
+    #[test]
+    fn test_breakpoint_at_start_pc() {
+        // NOP, NOP, JMP $0200
+        let mut cpu = cpu_with_program(&[0xEA, 0xEA, 0x4C, 0x00, 0x02], 0x0200);
+
+        cpu.breakpoints.add_pc(0x0200);
+
+        // Hit before the first instruction, the next run resumes past it
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Breakpoint { pc: 0x0200 })
+        );
+        assert_eq!(cpu.cycles, 0);
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Breakpoint { pc: 0x0200 })
+        );
+        assert_eq!(cpu.cycles, 7);
+
+        // A stop that was not a breakpoint is not skipped
+        assert_eq!(
+            cpu.run_until_pc(0x0201),
+            Ok(StopReason::Breakpoint { pc: 0x0201 })
+        );
+        cpu.breakpoints.add_opcode(0xEA);
+        assert_eq!(
+            cpu.run_instructions(10),
+            Ok(StopReason::Breakpoint { pc: 0x0201 })
+        );
+        assert_eq!(cpu.cycles, 9);
+    }
+
+    #[test]
+    fn test_conditional_breakpoints() {
+        // loop: INX, STX $0300, JMP loop
+        let mut cpu = cpu_with_program(&[0xE8, 0x8E, 0x00, 0x03, 0x4C, 0x00, 0x02], 0x0200);
+
+        let expression = "X >= 3 && [$0300] == X".parse().unwrap();
+        let condition = cpu.breakpoints.add_condition(expression, None, 1);
+
+        assert_eq!(
+            cpu.run_instructions(100),
+            Ok(StopReason::Breakpoint { pc: 0x0204 })
+        );
+        assert_eq!(cpu.registers.x, 3);
+
+        // Bound to a pc, the condition is hit on every arrival while it holds
+        assert!(cpu.breakpoints.remove_condition(condition));
+        cpu.breakpoints.add_condition("[$0300] & 1".parse().unwrap(), Some(0x0200), 2);
+
+        assert_eq!(
+            cpu.run_instructions(100),
+            Ok(StopReason::Breakpoint { pc: 0x0200 })
+        );
+        assert_eq!(cpu.registers.x, 5);
+        assert_eq!(cpu.breakpoints.condition_hits(0), Some(2));
+
+        assert_eq!(
+            cpu.run_instructions(100),
+            Ok(StopReason::Breakpoint { pc: 0x0200 })
+        );
+        assert_eq!(cpu.registers.x, 7);
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = asm6502! {
+                    .org $0200
+                    LDX #$02
+                    LDA ($10,X)
+                    STA $0300,X
+                    .byte $A7, $20      // LAX $20
+                    JMP $0200
+        };
+
+        let mut cpu = cpu_with_program(program.bytes, program.origin);
+        cpu.set_undocumented_opcodes(true);
+        cpu.memory.write_short(0x0012, 0x0400);
+        cpu.memory.write_byte(0x0400, 0x5A);
+        cpu.memory.write_byte(0x0020, 0x07);
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        cpu.set_tracer(move |entry: &TraceEntry| sink.borrow_mut().push(entry.to_string()));
+
+        cpu.run_instructions(4).unwrap();
+        cpu.tick().unwrap();
+
+        let expected = [
+            "0200  A2 02     LDX #$02                        A:00 X:00 Y:00 P:24 SP:FD CYC:0",
+            "0202  A1 10     LDA ($10,X) @ 12 = 0400 = 5A    A:00 X:02 Y:00 P:24 SP:FD CYC:2",
+            "0204  9D 00 03  STA $0300,X @ 0302 = 00         A:5A X:02 Y:00 P:24 SP:FD CYC:8",
+            "0207  A7 20    *LAX $20 = 07                    A:5A X:02 Y:00 P:24 SP:FD CYC:13",
+            "0209  4C 00 02  JMP $0200                       A:07 X:07 Y:00 P:24 SP:FD CYC:16",
+        ];
+
+        // The ticks of an instruction trace it once
+        cpu.tick().unwrap();
+        assert_eq!(*lines.borrow(), expected);
+
+        assert!(cpu.take_tracer().is_some());
+        cpu.step().unwrap();
+        assert_eq!(lines.borrow().len(), 5);
+    }
+
+    #[test]
+    fn test_trace_without_side_effects() {
+        let reads = Rc::new(Cell::new(0));
+        let mut bus = bus_with_counter(0xD000, 0x11, &reads);
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        // LDA $D000, STA $9000
+        bus.load(&[0xAD, 0x00, 0xD0, 0x8D, 0x00, 0x90], 0x0200).unwrap();
+
+        let mut cpu = Cpu::with_memory(bus);
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        cpu.set_tracer(move |entry: &TraceEntry| sink.borrow_mut().push(entry.text.clone()));
+
+        // Only LDA reads the device, the trace shows the data bus for it and
+        // does not fail on the unmapped $9000
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+
+        assert_eq!(reads.get(), 1);
+        assert_eq!(*lines.borrow(), ["LDA $D000 = 02", "STA $9000 = 11"]);
+    }
+
+    #[test]
+    fn test_step_errors() {
+        // PHA with sp at $00, PLA, $FF, LDA #
+        let mut cpu = cpu_with_program(&[0x48, 0x68, 0xFF, 0xA9], 0x0200);
+        cpu.registers.sp = 0x00;
+        cpu.registers.a = 0x42;
+
+        let outcome = StepOutcome::Executed {
+            opcode: 0x48,
+            cycles: 3,
+        };
+
+        // The stack pointer wraps within page 1
+        assert_eq!(cpu.step(), Ok(outcome));
+        assert_eq!(cpu.registers.sp, 0xFF);
+        assert_eq!(cpu.memory.read_byte(0x0100), 0x42);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.sp, 0x00);
+
+        cpu.set_strict(true);
+
+        let error = StepError::InvalidOpcode {
+            opcode: 0xFF,
+            address: 0x0202,
+        };
+
+        assert_eq!(cpu.step(), Err(error.clone()));
+        assert_eq!(cpu.step(), Err(error));
+        assert_eq!(cpu.registers.pc, 0x0202);
+
+        cpu.set_strict(false);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0203);
+    }
+
+    #[test]
+    fn test_undocumented_opcodes() {
+        // LDA #$F0, LDX #$3C, SAX $11, DCP $11, SLO $12, ALR #$0F, SBX #$01, LAX $10, ISC $13
+        let program = [
+            0xA9, 0xF0, 0xA2, 0x3C, 0x87, 0x11, 0xC7, 0x11, 0x07, 0x12, 0x4B, 0x0F, 0xCB, 0x01,
+            0xA7, 0x10, 0xE7, 0x13,
+        ];
+
+        let mut cpu = cpu_with_program(&program, 0x0200);
+        cpu.set_undocumented_opcodes(true);
+        cpu.memory.load(&[0x81, 0x00, 0x81, 0xFF], 0x0010).unwrap();
+
+        let cycles: Vec<u32> = (0..4).map(|_| step_cycles(&mut cpu)).collect();
+        assert_eq!(cycles, [2, 2, 3, 5]);
+        assert_eq!(cpu.memory.read_byte(0x0011), 0x2F);
+        assert!(cpu.registers.flags.get(Flag::Carry));
+        assert!(cpu.registers.flags.get(Flag::Negative));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.memory.read_byte(0x0012), 0x02);
+        assert_eq!(cpu.registers.a, 0xF2);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 0x01);
+        assert!(!cpu.registers.flags.get(Flag::Carry));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.x, 0xFF);
+        assert!(!cpu.registers.flags.get(Flag::Carry));
+
+        cpu.step().unwrap();
+        assert_eq!((cpu.registers.a, cpu.registers.x), (0x81, 0x81));
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.memory.read_byte(0x0013), 0x00);
+        assert_eq!(cpu.registers.a, 0x80);
+        assert!(cpu.registers.flags.get(Flag::Carry));
+
+        // Disabled undocumented opcodes are single byte NOPs
+        let mut cpu = cpu_with_program(&program[14..], 0x0200);
+
+        assert_eq!(step_cycles(&mut cpu), 2);
+        assert_eq!(cpu.registers.pc, 0x0201);
+        assert_eq!(cpu.registers.a, 0x00);
+    }
+
+    #[test]
+    fn test_read_modify_write_cycles() {
+        // INC $0300
+        let mut cpu = cpu_with_program(&[0xEE, 0x00, 0x03], 0x0200);
+        cpu.memory.write_byte(0x0300, 0x05);
+
+        cpu.step().unwrap();
+
+        let accesses: Vec<(u16, u8, BusDirection)> = cpu
+            .bus_cycles()
+            .iter()
+            .map(|cycle| (cycle.address, cycle.value, cycle.direction))
+            .collect();
+
+        assert_eq!(
+            accesses,
+            [
+                (0x0200, 0xEE, BusDirection::Read),
+                (0x0201, 0x00, BusDirection::Read),
+                (0x0202, 0x03, BusDirection::Read),
+                (0x0300, 0x05, BusDirection::Read),
+                (0x0300, 0x05, BusDirection::Write),
+                (0x0300, 0x06, BusDirection::Write),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tick_matches_step() {
+        // LDX #$FF, JSR $0210, INC $02FF,X, PHA, PLA, BNE -4, ..., ROL $0300, RTS
+        let mut program = vec![0xA2, 0xFF, 0x20, 0x10, 0x02, 0xFE, 0xFF, 0x02, 0x48, 0x68];
+        program.extend([0xD0, 0xFC]);
+        program.resize(0x10, 0xEA);
+        program.extend([0x2E, 0x00, 0x03, 0x60]);
+
+        let mut stepped = cpu_with_program(&program, 0x0200);
+        let mut ticked = cpu_with_program(&program, 0x0200);
+
+        for _ in 0..12 {
+            stepped.step().unwrap();
+
+            let mut cycles = Vec::new();
+            let start = ticked.cycles;
+
+            loop {
+                cycles.push(ticked.tick().unwrap());
+
+                if ticked.cycles != start {
+                    break;
+                }
+            }
+
+            assert_eq!(cycles, stepped.bus_cycles());
+            assert_eq!(ticked.bus_cycles(), stepped.bus_cycles());
+            assert_eq!(ticked.cycles, stepped.cycles);
+            assert_eq!(
+                format!("{:?}", ticked.registers),
+                format!("{:?}", stepped.registers)
+            );
+        }
+
+        assert_eq!(
+            ticked.memory.read_byte(0x0300),
+            stepped.memory.read_byte(0x0300)
+        );
+        assert_eq!(
+            ticked.memory.read_byte(0x03FE),
+            stepped.memory.read_byte(0x03FE)
+        );
+    }
+}
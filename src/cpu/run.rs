@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use super::{BusCycle, BusDirection, Cpu, HaltReason, StepError, StepOutcome, Voltage};
+use crate::memory::Memory;
+use crate::registers::Flag;
+
+// How many instructions a loop may span to be detected as a trap
+const TRAP_WINDOW: usize = 16;
+
+/// Why a run returned control to the host.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StopReason {
+    BudgetExhausted,        // The cycles or instructions of the run were used up
+    Breakpoint { pc: u16 }, // A breakpoint or the stop condition was hit
+    Watchpoint { pc: u16, access: BusCycle }, // The last instruction made a watched access
+    Halted(HaltReason),     // The cpu jammed or was stopped
+    Trapped { pc: u16 },    // The program is stuck in a loop on itself
+    Exited { code: i32 },   // A hook ended the program
+}
+
+// The registers after an instruction. Without writes in between, seeing them
+// again means that the cpu loops without a way out.
+#[derive(PartialEq)]
+struct LoopState {
+    pc: u16,
+    a: u8,
+    x: u8,
+    y: u8,
+    sp: u8,
+    p: u8,
+}
+
+#[derive(Default)]
+pub(super) struct TrapDetector {
+    states: VecDeque<LoopState>, // Since the last write, newest last
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// Makes runs stop with `StopReason::Trapped` once the program is stuck,
+    /// like in the `JMP *` or `BNE *` that test ROMs end in. A loop of a few
+    /// instructions that returns to the same registers without writing to
+    /// memory counts as stuck. Reads are not looked at, so a loop polling a
+    /// device for a change counts as stuck as well, and so does a loop with
+    /// interrupts enabled while none is pending. A loop is not stuck while an
+    /// interrupt is pending.
+    pub fn set_trap_detection(&mut self, enabled: bool) {
+        self.trap_detector = enabled.then(TrapDetector::default);
+    }
+
+    /// Runs whole instructions until at least `cycles` cycles have passed.
+    /// The overshoot of the last instruction can be read from `cycles`.
+    pub fn run_cycles(&mut self, cycles: u32) -> Result<StopReason, StepError> {
+        let start = self.cycles;
+
+        self.run(|cpu| cpu.cycles.wrapping_sub(start) >= cycles, |_| false)
+    }
+
+    pub fn run_instructions(&mut self, instructions: usize) -> Result<StopReason, StepError> {
+        let mut remaining = instructions;
+
+        self.run(
+            |_| match remaining {
+                0 => true,
+                _ => {
+                    remaining -= 1;
+                    false
+                }
+            },
+            |_| false,
+        )
+    }
+
+    /// Steps until `condition` holds after an instruction. Without a halt the
+    /// run only ends once the condition is met.
+    pub fn run_until(
+        &mut self,
+        condition: impl FnMut(&Cpu<M>) -> bool,
+    ) -> Result<StopReason, StepError> {
+        self.run(|_| false, condition)
+    }
+
+    /// Steps until the pc reaches `address`. The instruction at the current
+    /// pc is executed unless it has a breakpoint that was not hit yet, so a run can continue
+    /// from a previous stop.
+    pub fn run_until_pc(&mut self, address: u16) -> Result<StopReason, StepError> {
+        self.run_until(|cpu| cpu.registers.pc == address)
+    }
+
+    // Halts end a run like any other stop, the remaining step errors are
+    // passed on to the host.
+    fn run(
+        &mut self,
+        mut exhausted: impl FnMut(&Cpu<M>) -> bool,
+        mut condition: impl FnMut(&Cpu<M>) -> bool,
+    ) -> Result<StopReason, StepError> {
+        if let Some(detector) = &mut self.trap_detector {
+            detector.states.clear();
+        }
+
+        if let Some(reason) = self.check_start_breakpoint() {
+            return Ok(reason);
+        }
+
+        loop {
+            if exhausted(self) {
+                return Ok(StopReason::BudgetExhausted);
+            }
+
+            let outcome = match self.step() {
+                Ok(outcome) => outcome,
+                Err(StepError::Halted(reason)) => return Ok(StopReason::Halted(reason)),
+                Err(error) => return Err(error),
+            };
+
+            if let StepOutcome::Exited { code } = outcome {
+                return Ok(StopReason::Exited { code });
+            }
+
+            if let Some(reason) = self.break_hit {
+                return Ok(reason);
+            }
+
+            if self.is_trapped(outcome) {
+                return Ok(StopReason::Trapped {
+                    pc: self.registers.pc,
+                });
+            }
+
+            if condition(self) {
+                return Ok(StopReason::Breakpoint {
+                    pc: self.registers.pc,
+                });
+            }
+        }
+    }
+
+    // A 65C02 waiting for an interrupt is not stuck
+    fn is_trapped(&mut self, outcome: StepOutcome) -> bool {
+        let Some(detector) = &mut self.trap_detector else {
+            return false;
+        };
+
+        if outcome == StepOutcome::Waiting {
+            return false;
+        }
+
+        let pending = self.nmi_edge
+            || (self.irq_line == Voltage::Low && !self.registers.flags.get(Flag::InterruptDisable));
+
+        if pending {
+            detector.states.clear();
+            return false;
+        }
+
+        let state = LoopState {
+            pc: self.registers.pc,
+            a: self.registers.a,
+            x: self.registers.x,
+            y: self.registers.y,
+            sp: self.registers.sp,
+            p: self.registers.flags.0,
+        };
+
+        let wrote = self.bus_cycles.iter().any(|access| access.direction == BusDirection::Write);
+
+        if wrote {
+            detector.states.clear();
+        } else if detector.states.contains(&state) {
+            return true;
+        }
+
+        if detector.states.len() == TRAP_WINDOW {
+            detector.states.pop_front();
+        }
+
+        detector.states.push_back(state);
+
+        false
+    }
+}
@@ -0,0 +1,104 @@
+use super::{BusCycle, Cpu, HaltReason, StepError};
+use crate::instruction::Instruction;
+use crate::memory::Memory;
+use crate::registers::Registers;
+
+struct Snapshot<M: Memory + 'static> {
+    registers: Registers,
+    cycles: u32,
+    current_instruction: Option<&'static Instruction<M>>,
+    operand_address: Option<u16>,
+    page_crossed: bool,
+    branch_taken: bool,
+    halt_reason: Option<HaltReason>,
+    waiting: bool,
+}
+
+/// An instruction that has been started with `tick` but not finished yet.
+pub(super) struct TickState<M: Memory + 'static> {
+    snapshot: Snapshot<M>,
+    performed: Vec<BusCycle>,
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// Advances the cpu by a single cycle and returns the bus access made in
+    /// it. The registers only change once the last cycle of an instruction
+    /// has been ticked. Hooks are not run, ticks execute memory as it is.
+    /// KIL and STP halt the cpu on their last cycle, that tick returns the
+    /// halt reason and its access is left in `bus_cycles`.
+    pub fn tick(&mut self) -> Result<BusCycle, StepError> {
+        if let Some(reason) = self.halt_reason {
+            return Err(reason.into());
+        }
+
+        if self.tick_state.is_none() {
+            self.break_hit = None;
+        }
+
+        let TickState {
+            snapshot,
+            performed,
+        } = match self.tick_state.take() {
+            Some(state) => state,
+            None => TickState {
+                snapshot: self.snapshot(),
+                performed: Vec::new(),
+            },
+        };
+
+        // The instruction is executed from its start again. Accesses that
+        // were already performed are replayed, the next one goes to memory.
+        let nmi_edge = self.nmi_edge;
+        self.replay = Some(performed);
+        self.bus_cycles.clear();
+
+        let result = self.execute_step();
+
+        let mut performed = self.replay.take().unwrap_or_default();
+        let cycle = self.bus_cycles[performed.len()];
+        performed.push(cycle);
+
+        if self.bus_cycles.len() > performed.len() {
+            self.restore(&snapshot);
+            self.nmi_edge = nmi_edge;
+            self.bus_cycles = performed.clone();
+            self.tick_state = Some(TickState {
+                snapshot,
+                performed,
+            });
+
+            // Halting happens once the instruction completes
+            if let Err(StepError::Halted(_)) = result {
+                return Ok(cycle);
+            }
+        } else if result.is_ok() {
+            self.check_breakpoints();
+        }
+
+        result.map(|_| cycle)
+    }
+
+    fn snapshot(&self) -> Snapshot<M> {
+        Snapshot {
+            registers: self.registers.clone(),
+            cycles: self.cycles,
+            current_instruction: self.current_instruction,
+            operand_address: self.operand_address,
+            page_crossed: self.page_crossed,
+            branch_taken: self.branch_taken,
+            halt_reason: self.halt_reason,
+            waiting: self.waiting,
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot<M>) {
+        self.registers = snapshot.registers.clone();
+        self.cycles = snapshot.cycles;
+        self.current_instruction = snapshot.current_instruction;
+        self.operand_address = snapshot.operand_address;
+        self.page_crossed = snapshot.page_crossed;
+        self.branch_taken = snapshot.branch_taken;
+        self.halt_reason = snapshot.halt_reason;
+        self.waiting = snapshot.waiting;
+    }
+}
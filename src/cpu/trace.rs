@@ -0,0 +1,229 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+
+use super::{Cpu, Variant};
+use crate::disasm::{DisassembledInstruction, Disassembler};
+use crate::instruction::{AddressingMode, InstructionType};
+use crate::instruction_table::InstructionTable;
+use crate::memory::Memory;
+
+/// The state of the cpu before an instruction is executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub instruction: DisassembledInstruction,
+    pub text: String, // The disassembly with the values of the operand, like `STA $0200 = 5A`
+    pub undocumented: bool,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub sp: u8,
+    pub cycles: u32,
+}
+
+/// Formats the entry as a line of a nestest log, like
+/// `C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7`.
+/// Undocumented opcodes are marked with a `*` before the mnemonic.
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> =
+            self.instruction.bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+        let marker = if self.undocumented { '*' } else { ' ' };
+
+        write!(
+            f,
+            "{:04X}  {:<9}{marker}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+            self.instruction.address,
+            bytes.join(" "),
+            self.text,
+            self.a,
+            self.x,
+            self.y,
+            self.p,
+            self.sp,
+            self.cycles
+        )
+    }
+}
+
+/// Receives an entry before every instruction the cpu executes.
+pub trait Tracer {
+    fn trace(&mut self, entry: &TraceEntry);
+}
+
+impl<F: FnMut(&TraceEntry)> Tracer for F {
+    fn trace(&mut self, entry: &TraceEntry) {
+        self(entry)
+    }
+}
+
+/// Writes a nestest log line per instruction. Wrap files in a `BufWriter`,
+/// it is flushed when the tracer is dropped.
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    failed: bool,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(writer: W) -> TraceWriter<W> {
+        TraceWriter {
+            writer,
+            failed: false,
+        }
+    }
+}
+
+impl<W: Write> Tracer for TraceWriter<W> {
+    // Tracing stops at the first error rather than leaving gaps in the log
+    fn trace(&mut self, entry: &TraceEntry) {
+        if self.failed {
+            return;
+        }
+
+        if let Err(error) = writeln!(self.writer, "{entry}") {
+            log::warn!("Stopped writing the trace: {error}");
+            self.failed = true;
+        }
+    }
+}
+
+impl<M: Memory + 'static> Cpu<M> {
+    /// Sends an entry to `tracer` before every instruction. Replaces the
+    /// previous tracer.
+    pub fn set_tracer(&mut self, tracer: impl Tracer + 'static) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+        self.tracer.take()
+    }
+
+    // Called right before the opcode fetch. The entry is also logged at the
+    // trace level, so it is only built if somebody is listening.
+    pub(super) fn trace(&mut self) {
+        if self.tracer.is_none() && !log::log_enabled!(log::Level::Trace) {
+            return;
+        }
+
+        let entry = self.trace_entry();
+        log::trace!("{entry}");
+
+        if let Some(tracer) = &mut self.tracer {
+            tracer.trace(&entry);
+        }
+    }
+
+    // Memory is read with `Memory::peek`, so tracing has no side effects
+    fn trace_entry(&self) -> TraceEntry {
+        let pc = self.registers.pc;
+        let bytes: Vec<u8> =
+            (0..3).map(|offset| self.memory.peek(pc.wrapping_add(offset))).collect();
+
+        let mut disassembler = Disassembler::new(self.variant);
+        disassembler.set_undocumented_opcodes(self.undocumented_opcodes);
+
+        let instruction =
+            disassembler.decode(&bytes, pc).expect("Three bytes hold any instruction");
+
+        // nestest logs spell ISC as ISB
+        let text = match instruction.instruction_type {
+            InstructionType::ISC => instruction.to_string().replacen("ISC", "ISB", 1),
+            _ => instruction.to_string(),
+        };
+
+        TraceEntry {
+            text: text + &self.operand_values(&instruction),
+            undocumented: self.is_undocumented(instruction.opcode()),
+            instruction,
+            a: self.registers.a,
+            x: self.registers.x,
+            y: self.registers.y,
+            p: self.registers.flags.0,
+            sp: self.registers.sp,
+            cycles: self.cycles,
+        }
+    }
+
+    // The rows the documented table fills with NOPs and jams
+    fn is_undocumented(&self, opcode: u8) -> bool {
+        let instruction = &InstructionTable::<M>::INSTRUCTIONS[opcode as usize];
+
+        self.variant != Variant::Cmos65C02
+            && matches!(
+                instruction.instruction_type,
+                InstructionType::NOP | InstructionType::KIL
+            )
+            && opcode != 0xEA
+    }
+
+    // The addresses and values nestest shows after the operand
+    fn operand_values(&self, instruction: &DisassembledInstruction) -> String {
+        let operand = instruction.operand();
+        let value = |address: u16| self.memory.peek(address);
+
+        let zero_page_pointer = |pointer: u8| {
+            u16::from_le_bytes([value(pointer as u16), value(pointer.wrapping_add(1) as u16)])
+        };
+
+        match instruction.mode {
+            AddressingMode::ZeroPage => format!(" = {:02X}", value(operand)),
+            AddressingMode::Absolute => match instruction.instruction_type {
+                InstructionType::JMP | InstructionType::JSR => String::new(),
+                _ => format!(" = {:02X}", value(operand)),
+            },
+            AddressingMode::ZeroPageX | AddressingMode::ZeroPageY => {
+                let index = match instruction.mode {
+                    AddressingMode::ZeroPageX => self.registers.x,
+                    _ => self.registers.y,
+                };
+                let address = (operand as u8).wrapping_add(index) as u16;
+
+                format!(" @ {address:02X} = {:02X}", value(address))
+            }
+            AddressingMode::AbsoluteX | AddressingMode::AbsoluteY => {
+                let index = match instruction.mode {
+                    AddressingMode::AbsoluteX => self.registers.x,
+                    _ => self.registers.y,
+                };
+                let address = operand.wrapping_add(index as u16);
+
+                format!(" @ {address:04X} = {:02X}", value(address))
+            }
+            AddressingMode::Indirect => {
+                // The 65C02 fixed the page wrap of the pointer
+                let high_address = match self.variant {
+                    Variant::Cmos65C02 => operand.wrapping_add(1),
+                    _ => (operand & 0xFF00) | (operand.wrapping_add(1) & 0x00FF),
+                };
+
+                let target = u16::from_le_bytes([value(operand), value(high_address)]);
+
+                format!(" = {target:04X}")
+            }
+            AddressingMode::IndirectX => {
+                let pointer = (operand as u8).wrapping_add(self.registers.x);
+                let address = zero_page_pointer(pointer);
+
+                format!(" @ {pointer:02X} = {address:04X} = {:02X}", value(address))
+            }
+            AddressingMode::IndirectY => {
+                let base = zero_page_pointer(operand as u8);
+                let address = base.wrapping_add(self.registers.y as u16);
+
+                format!(" = {base:04X} @ {address:04X} = {:02X}", value(address))
+            }
+            AddressingMode::ZeroPageIndirect => {
+                let address = zero_page_pointer(operand as u8);
+
+                format!(" = {address:04X} = {:02X}", value(address))
+            }
+            AddressingMode::AbsoluteIndexedIndirect => {
+                let pointer = operand.wrapping_add(self.registers.x as u16);
+                let target = u16::from_le_bytes([value(pointer), value(pointer.wrapping_add(1))]);
+
+                format!(" @ {pointer:04X} = {target:04X}")
+            }
+            _ => String::new(),
+        }
+    }
+}
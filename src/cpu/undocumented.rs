@@ -0,0 +1,201 @@
+use super::instructions::{rotate_left, rotate_right, shift_left, shift_right};
+use crate::cpu::Cpu;
+use crate::memory::Memory;
+use crate::registers::Flag;
+
+// Value that is or'ed into the accumulator by the unstable ANE and LXA opcodes.
+// It depends on the chip and temperature, $EE is the most commonly seen one.
+const UNSTABLE_MAGIC: u8 = 0xEE;
+
+impl<M: Memory + 'static> Cpu<M> {
+    pub fn slo(&mut self) {
+        let value = self.replace_accumulator_or_memory_with_carry(shift_left);
+
+        self.registers.a |= value;
+
+        self.update_zero_flag(self.registers.a);
+        self.update_negative_flag(self.registers.a);
+    }
+
+    pub fn rla(&mut self) {
+        let value = self.replace_accumulator_or_memory_with_carry(rotate_left);
+
+        self.registers.a &= value;
+
+        self.update_zero_flag(self.registers.a);
+        self.update_negative_flag(self.registers.a);
+    }
+
+    pub fn sre(&mut self) {
+        let value = self.replace_accumulator_or_memory_with_carry(shift_right);
+
+        self.registers.a ^= value;
+
+        self.update_zero_flag(self.registers.a);
+        self.update_negative_flag(self.registers.a);
+    }
+
+    pub fn rra(&mut self) {
+        let value = self.replace_accumulator_or_memory_with_carry(rotate_right);
+
+        self.add_with_carry(value);
+    }
+
+    pub fn dcp(&mut self) {
+        let value = self.replace_accumulator_or_memory(|value| value.wrapping_sub(1));
+
+        self.compare(self.registers.a, value);
+    }
+
+    pub fn isc(&mut self) {
+        let value = self.replace_accumulator_or_memory(|value| value.wrapping_add(1));
+
+        self.subtract_with_carry(value);
+    }
+
+    pub fn sax(&mut self) {
+        let address = self.get_operand_address();
+
+        self.write(address, self.registers.a & self.registers.x);
+    }
+
+    pub fn lax(&mut self) {
+        let operand = self.get_operand_value();
+
+        self.registers.a = operand;
+        self.registers.x = operand;
+
+        self.update_zero_flag(operand);
+        self.update_negative_flag(operand);
+    }
+
+    pub fn lxa(&mut self) {
+        let operand = self.get_operand_value();
+
+        self.registers.a = (self.registers.a | UNSTABLE_MAGIC) & operand;
+        self.registers.x = self.registers.a;
+
+        self.update_zero_flag(self.registers.a);
+        self.update_negative_flag(self.registers.a);
+    }
+
+    pub fn ane(&mut self) {
+        let operand = self.get_operand_value();
+
+        self.registers.a = (self.registers.a | UNSTABLE_MAGIC) & self.registers.x & operand;
+
+        self.update_zero_flag(self.registers.a);
+        self.update_negative_flag(self.registers.a);
+    }
+
+    pub fn anc(&mut self) {
+        self.and();
+
+        let negative = self.registers.flags.get(Flag::Negative);
+        self.registers.flags.set(Flag::Carry, negative);
+    }
+
+    pub fn alr(&mut self) {
+        let operand = self.get_operand_value();
+        let (value, carry) = shift_right(self.registers.a & operand, 0);
+
+        self.registers.a = value;
+
+        self.registers.flags.set(Flag::Carry, carry);
+        self.update_zero_flag(value);
+        self.update_negative_flag(value);
+    }
+
+    pub fn arr(&mut self) {
+        let operand = self.get_operand_value();
+        let carry_in = self.registers.flags.get(Flag::Carry) as u8;
+
+        let and = self.registers.a & operand;
+        let (mut value, _) = rotate_right(and, carry_in);
+
+        if !self.decimal_mode() {
+            self.registers.a = value;
+
+            self.registers.flags.set(Flag::Carry, value & 0x40 != 0);
+            self.registers.flags.set(Flag::Overflow, ((value >> 6) ^ (value >> 5)) & 1 != 0);
+            self.update_zero_flag(value);
+            self.update_negative_flag(value);
+
+            return;
+        }
+
+        // In decimal mode the flags come from the rotated value before the BCD fixup
+        self.registers.flags.set(Flag::Negative, carry_in != 0);
+        self.registers.flags.set(Flag::Overflow, (and ^ value) & 0x40 != 0);
+        self.update_zero_flag(value);
+
+        if (and & 0x0F) + (and & 0x01) > 0x05 {
+            value = (value & 0xF0) | (value.wrapping_add(0x06) & 0x0F);
+        }
+
+        let carry = (and as u16 & 0xF0) + (and as u16 & 0x10) > 0x50;
+
+        if carry {
+            value = value.wrapping_add(0x60);
+        }
+
+        self.registers.a = value;
+        self.registers.flags.set(Flag::Carry, carry);
+    }
+
+    pub fn sbx(&mut self) {
+        let operand = self.get_operand_value();
+        let register = self.registers.a & self.registers.x;
+
+        self.compare(register, operand);
+
+        self.registers.x = register.wrapping_sub(operand);
+    }
+
+    pub fn las(&mut self) {
+        let operand = self.get_operand_value();
+        let value = operand & self.registers.sp;
+
+        self.registers.a = value;
+        self.registers.x = value;
+        self.registers.sp = value;
+
+        self.update_zero_flag(value);
+        self.update_negative_flag(value);
+    }
+
+    pub fn sha(&mut self) {
+        self.store_high_byte_and(self.registers.a & self.registers.x);
+    }
+
+    pub fn shx(&mut self) {
+        self.store_high_byte_and(self.registers.x);
+    }
+
+    pub fn shy(&mut self) {
+        self.store_high_byte_and(self.registers.y);
+    }
+
+    pub fn tas(&mut self) {
+        self.registers.sp = self.registers.a & self.registers.x;
+
+        self.store_high_byte_and(self.registers.sp);
+    }
+
+    // Stores `value` and'ed with the high byte of the base address plus one.
+    // When indexing crosses a page the stored value also replaces the high
+    // byte of the address written to.
+    fn store_high_byte_and(&mut self, value: u8) {
+        let address = self.get_operand_address();
+        let base_high = ((address >> 8) as u8).wrapping_sub(self.page_crossed as u8);
+
+        let value = value & base_high.wrapping_add(1);
+
+        let address = match self.page_crossed {
+            true => ((value as u16) << 8) | (address & 0x00FF),
+            false => address,
+        };
+
+        self.write(address, value);
+    }
+}
@@ -1,37 +1,70 @@
 use std::fs::File;
 use std::io::Read;
+use std::ops::RangeInclusive;
 
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Result};
 
-use crate::memory::Memory;
+use crate::memory::{Memory, MemoryError, RomWritePolicy};
 
 pub struct DefaultMemory {
     data: [u8; 1 << 16],
+    rom_regions: Vec<RangeInclusive<u16>>,
+    rom_write_policy: RomWritePolicy,
+    error: Option<MemoryError>,
 }
 
 impl DefaultMemory {
-    fn verify_executable(content_length: usize) -> Result<(usize, usize)> {
-        let start = 0xFFFA as usize - content_length;
-        let end = 0xFFFA;
+    /// Marks the range as read-only. Writes to it are handled according to
+    /// the rom write policy, loading data into it is still possible.
+    pub fn add_rom_region(&mut self, range: RangeInclusive<u16>) {
+        self.rom_regions.push(range);
+    }
 
-        // 32k binary limit
-        let max_binary_size = 1 << 15;
+    pub fn load_rom(&mut self, rom: &[u8], address: u16) -> Result<()> {
+        let end = address as usize + rom.len();
 
-        if content_length > max_binary_size {
+        if rom.is_empty() || end > 1 << 16 {
             return Err(anyhow!(
-                "Binary size ({content_length}) exceeds maximum size of {max_binary_size}"
+                "ROM of size {} does not fit at 0x{address:04X}",
+                rom.len()
             ));
         }
 
-        Ok((start, end))
+        self.data[address as usize..end].copy_from_slice(rom);
+        self.add_rom_region(address..=(end - 1) as u16);
+
+        Ok(())
+    }
+
+    pub fn set_rom_write_policy(&mut self, policy: RomWritePolicy) {
+        self.rom_write_policy = policy;
+    }
+
+    fn is_rom(&self, address: u16) -> bool {
+        self.rom_regions.iter().any(|region| region.contains(&address))
+    }
+
+    // Executables may fill the whole address space, like the 64K image of
+    // the functional tests
+    fn verify_executable(content_length: usize, address: u16) -> Result<()> {
+        if address as usize + content_length > 1 << 16 {
+            return Err(anyhow!(
+                "Binary of size {content_length} does not fit at 0x{address:04X}"
+            ));
+        }
+
+        Ok(())
     }
 }
 
 impl Memory for DefaultMemory {
     fn new() -> Self {
-        let mut data: [u8; 1 << 16] = [0u8; 1 << 16];
-
-        Self { data: data }
+        Self {
+            data: [0u8; 1 << 16],
+            rom_regions: Vec::new(),
+            rom_write_policy: RomWritePolicy::Ignore,
+            error: None,
+        }
     }
 
     fn read_byte(&self, address: u16) -> u8 {
@@ -39,20 +72,35 @@ impl Memory for DefaultMemory {
     }
 
     fn read_short(&self, address: u16) -> u16 {
-        u16::from_le_bytes([self.data[address as usize], self.data[address as usize + 1]])
+        u16::from_le_bytes([
+            self.read_byte(address),
+            self.read_byte(address.wrapping_add(1)),
+        ])
     }
 
     fn write_byte(&mut self, address: u16, value: u8) {
+        if self.is_rom(address) {
+            match self.rom_write_policy.handle(address, value) {
+                Ok(true) => {}
+                Ok(false) => return,
+                Err(error) => {
+                    self.error = Some(error);
+                    return;
+                }
+            }
+        }
+
         self.data[address as usize] = value;
     }
 
     fn write_short(&mut self, address: u16, value: u16) {
-        self.data[address as usize] = (value & 0xFFFF) as u8;
-        self.data[address as usize + 1] = (value >> 8) as u8;
+        self.write_byte(address, value as u8);
+        self.write_byte(address.wrapping_add(1), (value >> 8) as u8);
     }
 
     fn load(&mut self, executable: &[u8], address: u16) -> Result<()> {
-        let (start, end) = Self::verify_executable(executable.len())?;
+        Self::verify_executable(executable.len(), address)?;
+
         let start = address as usize;
         let end = start + executable.len();
 
@@ -63,12 +111,84 @@ impl Memory for DefaultMemory {
 
     fn load_from_file(&mut self, name: &str, address: u16) -> Result<()> {
         let mut file = File::open(name)?;
+        let length = file.metadata()?.len() as usize;
 
-        let start = address as usize;;
-        let end = start + (file.metadata()?.len() as usize);
+        Self::verify_executable(length, address)?;
+
+        let start = address as usize;
+        let end = start + length;
 
-        file.read(&mut self.data[start..end])?;
+        file.read_exact(&mut self.data[start..end])?;
 
         Ok(())
     }
+
+    fn take_error(&mut self) -> Option<MemoryError> {
+        self.error.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::Cpu;
+
+    #[test]
+    fn test_load() {
+        let mut memory = DefaultMemory::new();
+
+        assert!(memory.load(&[0xEA; 1 << 16], 0x0000).is_ok());
+        assert!(memory.load(&[0x4C, 0x00], 0xFFFE).is_ok());
+        assert!(memory.load(&[0x4C, 0x00, 0x02], 0xFFFE).is_err());
+        assert_eq!(memory.read_short(0xFFFE), 0x004C);
+    }
+
+    #[test]
+    fn test_rom_write_policy() {
+        let mut memory = DefaultMemory::new();
+
+        memory.load_rom(&[0xEA; 0x2000], 0xE000).unwrap();
+
+        memory.write_byte(0xDFFF, 0x12);
+        memory.write_byte(0xE000, 0x12);
+        memory.write_short(0xFFFE, 0x3456);
+
+        assert_eq!(memory.read_byte(0xDFFF), 0x12);
+        assert_eq!(memory.read_byte(0xE000), 0xEA);
+        assert_eq!(memory.read_short(0xFFFE), 0xEAEA);
+        assert_eq!(memory.take_error(), None);
+
+        memory.set_rom_write_policy(RomWritePolicy::Error);
+        memory.write_byte(0xF000, 0x34);
+
+        assert_eq!(memory.read_byte(0xF000), 0xEA);
+        assert_eq!(
+            memory.take_error(),
+            Some(MemoryError::RomWrite {
+                address: 0xF000,
+                value: 0x34
+            })
+        );
+
+        memory.set_rom_write_policy(RomWritePolicy::Allow);
+        memory.write_byte(0xF000, 0x34);
+
+        assert_eq!(memory.read_byte(0xF000), 0x34);
+        assert_eq!(memory.take_error(), None);
+    }
+
+    #[test]
+    fn test_rom_write_surfaces_in_cpu() {
+        let mut memory = DefaultMemory::new();
+
+        // PHA into a write protected stack page
+        memory.load(&[0x48], 0x0200).unwrap();
+        memory.load(&[0x00, 0x02], 0xFFFC).unwrap();
+        memory.add_rom_region(0x0100..=0x01FF);
+        memory.set_rom_write_policy(RomWritePolicy::Error);
+
+        let mut cpu = Cpu::with_memory(memory);
+
+        assert!(cpu.step().is_err());
+    }
 }
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+
+use crate::cpu::Variant;
+use crate::default_memory::DefaultMemory;
+use crate::instruction::{AddressingMode, InstructionType};
+use crate::instruction_table::InstructionTable;
+use crate::memory::Memory;
+
+/// An instruction decoded from memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>, // The opcode followed by the operand bytes
+    pub instruction_type: InstructionType,
+    pub mode: AddressingMode,
+    pub cycles: u8,
+    pub extra_cycle: bool,
+}
+
+impl DisassembledInstruction {
+    pub fn opcode(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// The operand bytes as a little endian value.
+    pub fn operand(&self) -> u16 {
+        match self.bytes[1..] {
+            [low] => low as u16,
+            [low, high] => u16::from_le_bytes([low, high]),
+            _ => 0,
+        }
+    }
+
+    /// The address a branch continues at when it is taken.
+    pub fn branch_target(&self) -> Option<u16> {
+        let (offset, next) = match self.mode {
+            AddressingMode::Relative => (self.bytes[1], 2),
+            AddressingMode::ZeroPageRelative => (self.bytes[2], 3),
+            _ => return None,
+        };
+
+        Some(self.address.wrapping_add(next).wrapping_add_signed(offset as i8 as i16))
+    }
+
+    pub fn mnemonic(&self) -> String {
+        self.instruction_type.mnemonic(self.opcode())
+    }
+
+    // Formats the operand in standard syntax, `name` renders zero page and
+    // absolute addresses
+    fn operand_text(&self, name: impl Fn(u16, bool) -> String) -> String {
+        let operand = self.operand();
+        let zero_page = || name(operand, true);
+        let absolute = || name(operand, false);
+
+        match self.mode {
+            AddressingMode::Implied => String::new(),
+            AddressingMode::Accumulator => String::from("A"),
+            AddressingMode::Immediate => format!("#${operand:02X}"),
+            AddressingMode::ZeroPage => zero_page(),
+            AddressingMode::ZeroPageX => format!("{},X", zero_page()),
+            AddressingMode::ZeroPageY => format!("{},Y", zero_page()),
+            AddressingMode::Absolute => absolute(),
+            AddressingMode::AbsoluteX => format!("{},X", absolute()),
+            AddressingMode::AbsoluteY => format!("{},Y", absolute()),
+            AddressingMode::Indirect => format!("({})", absolute()),
+            AddressingMode::IndirectX => format!("({},X)", zero_page()),
+            AddressingMode::IndirectY => format!("({}),Y", zero_page()),
+            AddressingMode::ZeroPageIndirect => format!("({})", zero_page()),
+            AddressingMode::AbsoluteIndexedIndirect => format!("({},X)", absolute()),
+            AddressingMode::Relative => name(self.branch_target().unwrap_or_default(), false),
+            AddressingMode::ZeroPageRelative => format!(
+                "{},{}",
+                name(self.bytes[1] as u16, true),
+                name(self.branch_target().unwrap_or_default(), false)
+            ),
+        }
+    }
+
+    fn text(&self, name: impl Fn(u16, bool) -> String) -> String {
+        match self.operand_text(name) {
+            operand if operand.is_empty() => self.mnemonic(),
+            operand => format!("{} {operand}", self.mnemonic()),
+        }
+    }
+}
+
+fn hex_address(address: u16, zero_page: bool) -> String {
+    match zero_page {
+        true => format!("${address:02X}"),
+        false => format!("${address:04X}"),
+    }
+}
+
+impl Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text(hex_address))
+    }
+}
+
+/// Decodes machine code with the instruction table of a variant and renders
+/// it in standard syntax, like `LDA ($20),Y` or `BNE $C012`.
+pub struct Disassembler {
+    variant: Variant,
+    undocumented_opcodes: bool,
+    show_bytes: bool,
+    show_cycles: bool,
+    symbols: HashMap<u16, String>,
+}
+
+impl Default for Disassembler {
+    fn default() -> Disassembler {
+        Disassembler::new(Variant::Nmos6502)
+    }
+}
+
+impl Disassembler {
+    pub fn new(variant: Variant) -> Disassembler {
+        Disassembler {
+            variant,
+            undocumented_opcodes: false,
+            show_bytes: false,
+            show_cycles: false,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Decodes the undocumented NMOS opcodes instead of treating them as NOPs.
+    pub fn set_undocumented_opcodes(&mut self, enabled: bool) {
+        self.undocumented_opcodes = enabled;
+    }
+
+    /// Adds the instruction bytes in hex to the lines.
+    pub fn set_show_bytes(&mut self, show: bool) {
+        self.show_bytes = show;
+    }
+
+    /// Adds the base cycles to the lines, with a `+` if a page crossing or a
+    /// taken branch costs more.
+    pub fn set_show_cycles(&mut self, show: bool) {
+        self.show_cycles = show;
+    }
+
+    /// Names an address. Operands with that address are rendered as the name.
+    pub fn add_symbol(&mut self, address: u16, name: impl Into<String>) {
+        self.symbols.insert(address, name.into());
+    }
+
+    pub fn symbol(&self, address: u16) -> Option<&str> {
+        self.symbols.get(&address).map(String::as_str)
+    }
+
+    /// Decodes the instruction at the start of `bytes`, which are located at
+    /// `address`. Returns `None` if the operand is cut off.
+    pub fn decode(&self, bytes: &[u8], address: u16) -> Option<DisassembledInstruction> {
+        let opcode = *bytes.first()?;
+        let instruction = &InstructionTable::<DefaultMemory>::for_variant(
+            self.variant,
+            self.undocumented_opcodes,
+        )[opcode as usize];
+
+        let size = 1 + instruction.mode.operand_size() as usize;
+
+        Some(DisassembledInstruction {
+            address,
+            bytes: bytes.get(..size)?.to_vec(),
+            instruction_type: instruction.instruction_type,
+            mode: instruction.mode,
+            cycles: instruction.cycles,
+            extra_cycle: instruction.extra_cycle,
+        })
+    }
+
+    /// Decodes instructions until the bytes run out or the last one is cut off.
+    pub fn decode_all(&self, bytes: &[u8], address: u16) -> Vec<DisassembledInstruction> {
+        let mut instructions = Vec::new();
+        let mut offset = 0;
+
+        while let Some(instruction) =
+            self.decode(&bytes[offset..], address.wrapping_add(offset as u16))
+        {
+            offset += instruction.bytes.len();
+            instructions.push(instruction);
+        }
+
+        instructions
+    }
+
+    /// Decodes the instructions starting within `range`. The last operand may
+    /// extend past the range. Memory is read with `Memory::peek`, so devices
+    /// do not see those reads.
+    pub fn decode_memory<M: Memory>(
+        &self,
+        memory: &M,
+        range: RangeInclusive<u16>,
+    ) -> Vec<DisassembledInstruction> {
+        let mut instructions = Vec::new();
+        let mut address = *range.start() as u32;
+
+        while address <= *range.end() as u32 {
+            let bytes: Vec<u8> = (0..3)
+                .map(|offset| memory.peek((address as u16).wrapping_add(offset)))
+                .collect();
+
+            let Some(instruction) = self.decode(&bytes, address as u16) else {
+                break;
+            };
+
+            address += instruction.bytes.len() as u32;
+            instructions.push(instruction);
+        }
+
+        instructions
+    }
+
+    /// The instruction in standard syntax with symbols substituted.
+    pub fn text(&self, instruction: &DisassembledInstruction) -> String {
+        instruction.text(|address, zero_page| match self.symbol(address) {
+            Some(name) => name.to_string(),
+            None => hex_address(address, zero_page),
+        })
+    }
+
+    /// The instruction as a listing line, `C000  A9 3F     LDA #$3F          ; 2`
+    /// with all options enabled.
+    pub fn line(&self, instruction: &DisassembledInstruction) -> String {
+        let mut line = format!("{:04X}  ", instruction.address);
+
+        if self.show_bytes {
+            let bytes: Vec<String> =
+                instruction.bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+
+            line += &format!("{:<8}  ", bytes.join(" "));
+        }
+
+        let text = self.text(instruction);
+
+        match self.show_cycles {
+            true => {
+                let variable = instruction.extra_cycle || instruction.branch_target().is_some();
+                let plus = if variable { "+" } else { "" };
+
+                line += &format!("{text:<16}  ; {}{plus}", instruction.cycles);
+            }
+            false => line += &text,
+        }
+
+        line
+    }
+
+    /// Disassembles `bytes` into a listing with a line per instruction. Named
+    /// addresses get a label line.
+    pub fn disassemble(&self, bytes: &[u8], address: u16) -> String {
+        let mut listing = String::new();
+
+        for instruction in self.decode_all(bytes, address) {
+            if let Some(name) = self.symbol(instruction.address) {
+                listing += &format!("{name}:\n");
+            }
+
+            listing += &self.line(&instruction);
+            listing.push('\n');
+        }
+
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bus, UnmappedReadPolicy};
+
+    #[test]
+    fn test_disassemble() {
+        let disassembler = Disassembler::default();
+
+        // LDA ($20),Y, BNE -4, JMP ($12FF), ASL A, STA $0300,X
+        let program = [
+            0xB1, 0x20, 0xD0, 0xFC, 0x6C, 0xFF, 0x12, 0x0A, 0x9D, 0x00, 0x03, 0xA9,
+        ];
+        let instructions = disassembler.decode_all(&program, 0xC010);
+
+        let text: Vec<String> =
+            instructions.iter().map(|instruction| instruction.to_string()).collect();
+        assert_eq!(
+            text,
+            [
+                "LDA ($20),Y",
+                "BNE $C010",
+                "JMP ($12FF)",
+                "ASL A",
+                "STA $0300,X"
+            ]
+        );
+        assert_eq!(instructions[1].branch_target(), Some(0xC010));
+
+        let mut disassembler = Disassembler::new(Variant::Cmos65C02);
+        disassembler.set_show_bytes(true);
+        disassembler.set_show_cycles(true);
+        disassembler.add_symbol(0xC000, "loop");
+        disassembler.add_symbol(0x0012, "flags");
+
+        // BBS3 $12,loop, LDA (flags), RMB7 $34
+        let listing = disassembler.disassemble(&[0xBF, 0x12, 0xFD, 0xB2, 0x12, 0x77, 0x34], 0xC000);
+        let expected = [
+            "loop:",
+            "C000  BF 12 FD  BBS3 flags,loop   ; 5+",
+            "C003  B2 12     LDA (flags)       ; 5",
+            "C005  77 34     RMB7 $34          ; 5",
+        ];
+
+        assert_eq!(listing.lines().collect::<Vec<_>>(), expected);
+
+        let mut memory = DefaultMemory::new();
+        memory.load(&[0xEA, 0x20, 0x00, 0x80], 0x0200).unwrap();
+
+        let instructions = Disassembler::default().decode_memory(&memory, 0x0200..=0x0201);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].to_string(), "JSR $8000");
+    }
+
+    #[test]
+    fn test_decode_memory_without_side_effects() {
+        let mut bus = Bus::new();
+        bus.map_ram(0x0000..=0x00FF);
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        bus.write_byte(0x00FF, 0xA9);
+
+        // The operand of LDA # is read from the data bus past the RAM
+        let instructions = Disassembler::default().decode_memory(&bus, 0x00FF..=0x00FF);
+
+        assert_eq!(instructions[0].to_string(), "LDA #$A9");
+        assert_eq!(bus.take_error(), None);
+    }
+}
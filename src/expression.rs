@@ -0,0 +1,403 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use crate::memory::Memory;
+use crate::registers::{Flag, Registers};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Register {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    P,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum UnaryOperator {
+    Not,
+    Negate,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Subtract,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(i32),
+    Register(Register),
+    Flag(Flag),
+    Memory(Box<Node>), // Byte at the address
+    Unary(UnaryOperator, Box<Node>),
+    Binary(BinaryOperator, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i32),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+// Longer operators first, so that `<=` is not taken for `<`
+const OPERATORS: [&str; 19] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "&", "|", "^", "+", "-", "!", "(", ")", "[", "]",
+    ".",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let position = source.len() - rest.len();
+
+        let (token, length) = if c == '$' || c == '%' || c.is_ascii_digit() {
+            let (radix, digits) = match c {
+                '$' => (16, &rest[1..]),
+                '%' => (2, &rest[1..]),
+                _ if rest.starts_with("0x") => (16, &rest[2..]),
+                _ => (10, rest),
+            };
+
+            let length = digits.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(digits.len());
+
+            let number = i32::from_str_radix(&digits[..length], radix)
+                .map_err(|_| anyhow!("Invalid number at position {position}"))?;
+
+            (Token::Number(number), rest.len() - digits.len() + length)
+        } else if c.is_ascii_alphabetic() {
+            let length = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+
+            (
+                Token::Identifier(rest[..length].to_ascii_uppercase()),
+                length,
+            )
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(|| anyhow!("Unexpected character '{c}' at position {position}"))?;
+
+            (Token::Operator(operator), operator.len())
+        };
+
+        tokens.push(token);
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+// Operators of the binary precedence levels, loosest first
+const PRECEDENCE: [&[(&str, BinaryOperator)]; 6] = [
+    &[("||", BinaryOperator::Or)],
+    &[("&&", BinaryOperator::And)],
+    &[
+        ("==", BinaryOperator::Equal),
+        ("!=", BinaryOperator::NotEqual),
+        ("<", BinaryOperator::Less),
+        ("<=", BinaryOperator::LessEqual),
+        (">", BinaryOperator::Greater),
+        (">=", BinaryOperator::GreaterEqual),
+    ],
+    &[("|", BinaryOperator::BitOr), ("^", BinaryOperator::BitXor)],
+    &[("&", BinaryOperator::BitAnd)],
+    &[("+", BinaryOperator::Add), ("-", BinaryOperator::Subtract)],
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        token
+    }
+
+    fn eat(&mut self, operator: &str) -> bool {
+        let matches = matches!(self.peek(), Some(Token::Operator(op)) if *op == operator);
+
+        if matches {
+            self.position += 1;
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<()> {
+        match self.eat(operator) {
+            true => Ok(()),
+            false => Err(anyhow!("Expected '{operator}'")),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Node> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+
+        let mut node = self.binary(level + 1)?;
+
+        while let Some(&(_, operator)) = operators.iter().find(|(op, _)| self.eat(op)) {
+            let right = self.binary(level + 1)?;
+
+            node = Node::Binary(operator, Box::new(node), Box::new(right));
+        }
+
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        if self.eat("!") {
+            return Ok(Node::Unary(UnaryOperator::Not, Box::new(self.unary()?)));
+        }
+
+        if self.eat("-") {
+            return Ok(Node::Unary(UnaryOperator::Negate, Box::new(self.unary()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Node::Number(number)),
+            Some(Token::Operator("(")) => {
+                let node = self.binary(0)?;
+                self.expect(")")?;
+
+                Ok(node)
+            }
+            Some(Token::Operator("[")) => {
+                let node = self.binary(0)?;
+                self.expect("]")?;
+
+                Ok(Node::Memory(Box::new(node)))
+            }
+            Some(Token::Identifier(name)) if name == "P" && self.eat(".") => self.flag(),
+            Some(Token::Identifier(name)) => {
+                let register = match name.as_str() {
+                    "A" => Register::A,
+                    "X" => Register::X,
+                    "Y" => Register::Y,
+                    "SP" | "S" => Register::Sp,
+                    "PC" => Register::Pc,
+                    "P" => Register::P,
+                    _ => return Err(anyhow!("Unknown register {name}")),
+                };
+
+                Ok(Node::Register(register))
+            }
+            Some(token) => Err(anyhow!("Unexpected {token:?}")),
+            None => Err(anyhow!("Unexpected end of expression")),
+        }
+    }
+
+    fn flag(&mut self) -> Result<Node> {
+        let flag = match self.next() {
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "N" => Flag::Negative,
+                "V" => Flag::Overflow,
+                "B" => Flag::Break,
+                "D" => Flag::Decimal,
+                "I" => Flag::InterruptDisable,
+                "Z" => Flag::Zero,
+                "C" => Flag::Carry,
+                _ => return Err(anyhow!("Unknown flag {name}")),
+            },
+            _ => return Err(anyhow!("Expected a flag after 'P.'")),
+        };
+
+        Ok(Node::Flag(flag))
+    }
+}
+
+/// A condition over the registers, flags and memory, like
+/// `A == $3F && [$0200] > 10 && P.C`.
+///
+/// Numbers are decimal, `$` or `0x` hex, or `%` binary. `[address]` reads a
+/// byte from memory and `P.C` style names read single flags. Comparisons and
+/// logical operators give 1 or 0, any value but 0 counts as true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+
+        let node = parser.binary(0)?;
+
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected {token:?} after the expression"));
+        }
+
+        Ok(Expression {
+            source: source.trim().to_string(),
+            node,
+        })
+    }
+
+    /// Evaluates the expression. Memory is read with `Memory::peek`, so the
+    /// evaluation has no side effects.
+    pub fn evaluate<M: Memory>(&self, registers: &Registers, memory: &M) -> i32 {
+        evaluate(&self.node, registers, memory)
+    }
+
+    pub fn is_true<M: Memory>(&self, registers: &Registers, memory: &M) -> bool {
+        self.evaluate(registers, memory) != 0
+    }
+}
+
+impl FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Expression::parse(source)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn evaluate<M: Memory>(node: &Node, registers: &Registers, memory: &M) -> i32 {
+    match node {
+        Node::Number(number) => *number,
+        Node::Register(register) => match register {
+            Register::A => registers.a as i32,
+            Register::X => registers.x as i32,
+            Register::Y => registers.y as i32,
+            Register::Sp => registers.sp as i32,
+            Register::Pc => registers.pc as i32,
+            Register::P => registers.flags.0 as i32,
+        },
+        Node::Flag(flag) => registers.flags.get(*flag) as i32,
+        Node::Memory(address) => {
+            let address = evaluate(address, registers, memory);
+
+            memory.peek(address as u16) as i32
+        }
+        Node::Unary(operator, node) => {
+            let value = evaluate(node, registers, memory);
+
+            match operator {
+                UnaryOperator::Not => (value == 0) as i32,
+                UnaryOperator::Negate => value.wrapping_neg(),
+            }
+        }
+        Node::Binary(operator, left, right) => {
+            let left = evaluate(left, registers, memory);
+
+            // The logical operators short circuit, which skips memory reads
+            match operator {
+                BinaryOperator::Or if left != 0 => return 1,
+                BinaryOperator::And if left == 0 => return 0,
+                _ => {}
+            }
+
+            let right = evaluate(right, registers, memory);
+
+            match operator {
+                BinaryOperator::Or | BinaryOperator::And => (right != 0) as i32,
+                BinaryOperator::Equal => (left == right) as i32,
+                BinaryOperator::NotEqual => (left != right) as i32,
+                BinaryOperator::Less => (left < right) as i32,
+                BinaryOperator::LessEqual => (left <= right) as i32,
+                BinaryOperator::Greater => (left > right) as i32,
+                BinaryOperator::GreaterEqual => (left >= right) as i32,
+                BinaryOperator::BitOr => left | right,
+                BinaryOperator::BitXor => left ^ right,
+                BinaryOperator::BitAnd => left & right,
+                BinaryOperator::Add => left.wrapping_add(right),
+                BinaryOperator::Subtract => left.wrapping_sub(right),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bus, UnmappedReadPolicy};
+    use crate::default_memory::DefaultMemory;
+
+    #[test]
+    fn test_evaluate() {
+        let mut memory = DefaultMemory::new();
+        memory.write_byte(0x0200, 11);
+        memory.write_byte(0x0210, 0x80);
+
+        let mut registers = Registers::new();
+        registers.a = 0x3F;
+        registers.x = 0x10;
+        registers.flags.set(Flag::Carry, true);
+
+        let expression = Expression::parse("A == $3F && [$0200] > 10 && P.C").unwrap();
+        assert!(expression.is_true(&registers, &memory));
+
+        let cases = [
+            ("[$0200 + x] & %10000000", 0x80),
+            ("a - 0x40", -1),
+            ("!p.z || [$0300]", 1),
+            ("(1 + 2) & 6 == 2", 1),
+            ("sp <= $FF", 1),
+            ("P", 0x25),
+        ];
+
+        for (source, value) in cases {
+            let expression: Expression = source.parse().unwrap();
+            assert_eq!(expression.evaluate(&registers, &memory), value, "{source}");
+        }
+
+        for source in ["A ==", "[$0200", "Q > 1", "P.Q", "A # 1", "1 2"] {
+            assert!(Expression::parse(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_without_side_effects() {
+        let mut bus = Bus::new();
+        bus.map_ram(0x0000..=0x00FF);
+        bus.set_unmapped_read_policy(UnmappedReadPolicy::Error);
+        bus.write_byte(0x0010, 0x07);
+
+        let expression = Expression::parse("[$10] + [$4000]").unwrap();
+
+        assert_eq!(expression.evaluate(&Registers::new(), &bus), 0x0E);
+        assert_eq!(bus.take_error(), None);
+    }
+}
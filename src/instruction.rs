@@ -1,8 +1,11 @@
-use crate::cpu::{Cpu, Operand};
+use std::fmt::{self, Debug, Formatter};
 
-type InstructionFn = fn(&mut Cpu);
+use crate::cpu::Cpu;
+use crate::memory::Memory;
 
-#[derive(Debug, PartialEq)]
+pub type InstructionFn<M> = fn(&mut Cpu<M>);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum AddressingMode {
     Accumulator, // Acc, 1 byte
     Immediate,   // 8 bit operand, 1 byte
@@ -14,9 +17,14 @@ pub enum AddressingMode {
     AbsoluteY,   // Y + 16 bit address offset, 3 bytes
     Relative,    // 8 bit offset for jump, 2 bytes
     Indirect,    // 16 bit address, 3
-    IndirectX,   // todo
-    IndirectY,   // todo
+    IndirectX,   // Zp pointer indexed by X, 2 bytes
+    IndirectY,   // Zp pointer, Y added to the address, 2 bytes
     Implied,     // No operand
+
+    // 65C02 only
+    ZeroPageIndirect,        // Zp pointer, 2 bytes
+    AbsoluteIndexedIndirect, // 16 bit pointer indexed by X, 3 bytes
+    ZeroPageRelative,        // Zp address followed by an 8 bit offset for jump, 3 bytes
 }
 
 impl AddressingMode {
@@ -32,15 +40,19 @@ impl AddressingMode {
             AddressingMode::AbsoluteY => 2,
             AddressingMode::Relative => 1,
             AddressingMode::Indirect => 2,
-            AddressingMode::IndirectX => 2,
-            AddressingMode::IndirectY => 2,
+            AddressingMode::IndirectX => 1,
+            AddressingMode::IndirectY => 1,
             AddressingMode::Implied => 0,
+            AddressingMode::ZeroPageIndirect => 1,
+            AddressingMode::AbsoluteIndexedIndirect => 2,
+            AddressingMode::ZeroPageRelative => 2,
         }
     }
 }
 
 #[rustfmt::skip]
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum InstructionType {
     ADC, AND, ASL,
     BCC, BCS, BEQ,
@@ -61,20 +73,61 @@ pub enum InstructionType {
     STA, STX, STY,
     TAX, TAY, TSX,
     TXA, TXS, TYA,
+
+    // Undocumented NMOS instructions
+    ALR, ANC, ANE,
+    ARR, DCP, ISC,
+    LAS, LAX, LXA,
+    RLA, RRA, SAX,
+    SBX, SHA, SHX,
+    SHY, SLO, SRE,
+    TAS,
+
+    // 65C02 instructions
+    BBR, BBS, BRA,
+    PHX, PHY, PLX,
+    PLY, RMB, SMB,
+    STP, STZ, TRB,
+    TSB, WAI,
 }
 
-#[derive(Debug)]
-pub struct Instruction {
+impl InstructionType {
+    /// The mnemonic of the instruction with the given opcode. The Rockwell bit
+    /// instructions carry their bit number, like `RMB3` or `BBS7`.
+    pub fn mnemonic(&self, opcode: u8) -> String {
+        match self {
+            InstructionType::RMB
+            | InstructionType::SMB
+            | InstructionType::BBR
+            | InstructionType::BBS => format!("{self:?}{}", (opcode >> 4) & 0x07),
+            _ => format!("{self:?}"),
+        }
+    }
+}
+
+pub struct Instruction<M: Memory + 'static> {
     pub opcode: u8,
     pub instruction_type: InstructionType,
     pub mode: AddressingMode,
     pub cycles: u8,
     pub extra_cycle: bool, // Adds extra cycle if page boundary is crossed,
-    pub fun: InstructionFn,
+    pub fun: InstructionFn<M>,
+}
+
+impl<M: Memory + 'static> Debug for Instruction<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instruction")
+            .field("opcode", &self.opcode)
+            .field("instruction_type", &self.instruction_type)
+            .field("mode", &self.mode)
+            .field("cycles", &self.cycles)
+            .field("extra_cycle", &self.extra_cycle)
+            .finish()
+    }
 }
 
-impl Instruction {
-    pub fn invalid(opcode: u8) -> Instruction {
+impl<M: Memory + 'static> Instruction<M> {
+    pub fn invalid(opcode: u8) -> Instruction<M> {
         Instruction {
             opcode,
             instruction_type: InstructionType::NOP,
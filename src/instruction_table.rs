@@ -1,5 +1,8 @@
-use crate::cpu::Cpu;
+use std::marker::PhantomData;
+
+use crate::cpu::{Cpu, Variant};
 use crate::instruction::{AddressingMode, Instruction, InstructionType};
+use crate::memory::Memory;
 
 macro_rules! instruction_table {
     ($($opcode: expr, $instruction_type: expr, $mode: expr, $cycles: expr, $extra_cycle: expr, $fun: expr);+) => {
@@ -9,261 +12,799 @@ macro_rules! instruction_table {
     }
 }
 
-pub const INSTRUCTIONS: &'static [Instruction] = instruction_table! {
-    0x00, InstructionType::BRK, AddressingMode::Implied, 0, false, Cpu::brk;
-    0x01, InstructionType::ORA, AddressingMode::IndirectX, 0, false, Cpu::ora;
-    0x02, InstructionType::KIL, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x03, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x04, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x05, InstructionType::ORA, AddressingMode::ZeroPage, 0, false, Cpu::ora;
-    0x06, InstructionType::ASL, AddressingMode::ZeroPage, 0, false, Cpu::asl;
-    0x07, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x08, InstructionType::PHP, AddressingMode::Implied, 0, false, Cpu::php;
-    0x09, InstructionType::ORA, AddressingMode::Immediate, 0, false, Cpu::ora;
-    0x0A, InstructionType::ASL, AddressingMode::Accumulator, 0, false, Cpu::asl;
-    0x0B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x0C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x0D, InstructionType::ORA, AddressingMode::Absolute, 0, false, Cpu::ora;
-    0x0E, InstructionType::ASL, AddressingMode::Absolute, 0, false, Cpu::asl;
-    0x0F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x10, InstructionType::BPL, AddressingMode::Relative, 0, false, Cpu::bpl;
-    0x11, InstructionType::ORA, AddressingMode::IndirectY, 0, false, Cpu::ora;
-    0x12, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x13, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x14, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x15, InstructionType::ORA, AddressingMode::ZeroPageX, 0, false, Cpu::ora;
-    0x16, InstructionType::ASL, AddressingMode::ZeroPageX, 0, false, Cpu::asl;
-    0x17, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x18, InstructionType::CLC, AddressingMode::Implied, 0, false, Cpu::clc;
-    0x19, InstructionType::ORA, AddressingMode::AbsoluteY, 0, false, Cpu::ora;
-    0x1A, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x1B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x1C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x1D, InstructionType::ORA, AddressingMode::AbsoluteX, 0, false, Cpu::ora;
-    0x1E, InstructionType::ASL, AddressingMode::AbsoluteX, 0, false, Cpu::asl;
-    0x1F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x20, InstructionType::JSR, AddressingMode::Absolute, 0, false, Cpu::jsr;
-    0x21, InstructionType::AND, AddressingMode::IndirectX, 0, false, Cpu::and;
-    0x22, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x23, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x24, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x25, InstructionType::BIT, AddressingMode::ZeroPage, 0, false, Cpu::bit;
-    0x26, InstructionType::AND, AddressingMode::ZeroPage, 0, false, Cpu::and;
-    0x27, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x28, InstructionType::ROL, AddressingMode::ZeroPage, 0, false, Cpu::rol;
-    0x29, InstructionType::PLP, AddressingMode::Implied, 0, false, Cpu::plp;
-    0x2A, InstructionType::AND, AddressingMode::Immediate, 0, false, Cpu::and;
-    0x2B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x2C, InstructionType::ROL, AddressingMode::Accumulator, 0, false, Cpu::rol;
-    0x2D, InstructionType::BIT, AddressingMode::Absolute, 0, false, Cpu::bit;
-    0x2E, InstructionType::AND, AddressingMode::Absolute, 0, false, Cpu::and;
-    0x2F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x30, InstructionType::ROL, AddressingMode::Absolute, 0, false, Cpu::rol;
-    0x31, InstructionType::BMI, AddressingMode::Relative, 0, false, Cpu::bmi;
-    0x32, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x33, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x34, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x35, InstructionType::AND, AddressingMode::IndirectY, 0, false, Cpu::and;
-    0x36, InstructionType::AND, AddressingMode::ZeroPageX, 0, false, Cpu::and;
-    0x37, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x38, InstructionType::SEC, AddressingMode::Implied, 0, false, Cpu::sec;
-    0x39, InstructionType::AND, AddressingMode::AbsoluteY, 0, false, Cpu::and;
-    0x3A, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x3B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x3C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x3D, InstructionType::AND, AddressingMode::AbsoluteX, 0, false, Cpu::and;
-    0x3E, InstructionType::ROL, AddressingMode::AbsoluteX, 0, false, Cpu::rol;
-    0x3F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x40, InstructionType::RTI, AddressingMode::Implied, 0, false, Cpu::rti;
-    0x41, InstructionType::EOR, AddressingMode::IndirectX, 0, false, Cpu::eor;
-    0x42, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x43, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x44, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x45, InstructionType::EOR, AddressingMode::ZeroPage, 0, false, Cpu::eor;
-    0x46, InstructionType::LSR, AddressingMode::ZeroPage, 0, false, Cpu::lsr;
-    0x47, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x48, InstructionType::PHA, AddressingMode::Implied, 0, false, Cpu::pha;
-    0x49, InstructionType::EOR, AddressingMode::Immediate, 0, false, Cpu::eor;
-    0x4A, InstructionType::LSR, AddressingMode::Accumulator, 0, false, Cpu::lsr;
-    0x4B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x4C, InstructionType::JMP, AddressingMode::Absolute, 0, false, Cpu::jmp;
-    0x4D, InstructionType::EOR, AddressingMode::Absolute, 0, false, Cpu::eor;
-    0x4E, InstructionType::LSR, AddressingMode::Absolute, 0, false, Cpu::lsr;
-    0x4F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x50, InstructionType::BVC, AddressingMode::Relative, 0, false, Cpu::bvc;
-    0x51, InstructionType::EOR, AddressingMode::IndirectY, 0, false, Cpu::eor;
-    0x52, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x53, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x54, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x55, InstructionType::EOR, AddressingMode::ZeroPageX, 0, false, Cpu::eor;
-    0x56, InstructionType::LSR, AddressingMode::ZeroPageX, 0, false, Cpu::lsr;
-    0x57, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x58, InstructionType::CLI, AddressingMode::Implied, 0, false, Cpu::cli;
-    0x59, InstructionType::EOR, AddressingMode::AbsoluteY, 0, false, Cpu::eor;
-    0x5A, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x5B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x5C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x5D, InstructionType::EOR, AddressingMode::AbsoluteX, 0, false, Cpu::eor;
-    0x5E, InstructionType::LSR, AddressingMode::AbsoluteX, 0, false, Cpu::lsr;
-    0x5F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x60, InstructionType::RTS, AddressingMode::Implied, 0, false, Cpu::rts;
-    0x61, InstructionType::ADC, AddressingMode::IndirectX, 0, false, Cpu::adc;
-    0x62, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x63, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x64, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x65, InstructionType::ADC, AddressingMode::ZeroPage, 0, false, Cpu::adc;
-    0x66, InstructionType::ROR, AddressingMode::ZeroPage, 0, false, Cpu::ror;
-    0x67, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x68, InstructionType::PLA, AddressingMode::Implied, 0, false, Cpu::pla;
-    0x69, InstructionType::ADC, AddressingMode::Immediate, 0, false, Cpu::adc;
-    0x6A, InstructionType::ROR, AddressingMode::Accumulator, 0, false, Cpu::ror;
-    0x6B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x6C, InstructionType::JMP, AddressingMode::Indirect, 0, false, Cpu::jmp;
-    0x6D, InstructionType::ADC, AddressingMode::Absolute, 0, false, Cpu::adc;
-    0x6E, InstructionType::ROR, AddressingMode::Absolute, 0, false, Cpu::ror;
-    0x6F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x70, InstructionType::BVS, AddressingMode::Relative, 0, false, Cpu::bvs;
-    0x71, InstructionType::ADC, AddressingMode::IndirectY, 0, false, Cpu::adc;
-    0x72, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x73, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x74, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x75, InstructionType::ADC, AddressingMode::ZeroPageX, 0, false, Cpu::adc;
-    0x76, InstructionType::ROR, AddressingMode::ZeroPageX, 0, false, Cpu::ror;
-    0x77, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x78, InstructionType::SEI, AddressingMode::Implied, 0, false, Cpu::sei;
-    0x79, InstructionType::ADC, AddressingMode::AbsoluteY, 0, false, Cpu::adc;
-    0x7A, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x7B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x7C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x7D, InstructionType::ADC, AddressingMode::AbsoluteX, 0, false, Cpu::adc;
-    0x7E, InstructionType::ROR, AddressingMode::AbsoluteX, 0, false, Cpu::ror;
-    0x7F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x80, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x81, InstructionType::STA, AddressingMode::IndirectX, 0, false, Cpu::sta;
-    0x82, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x83, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x84, InstructionType::STY, AddressingMode::ZeroPage, 0, false, Cpu::sty;
-    0x85, InstructionType::STA, AddressingMode::ZeroPage, 0, false, Cpu::sta;
-    0x86, InstructionType::STX, AddressingMode::ZeroPage, 0, false, Cpu::stx;
-    0x87, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x88, InstructionType::DEY, AddressingMode::Implied, 0, false, Cpu::dey;
-    0x89, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x8A, InstructionType::TXA, AddressingMode::Implied, 0, false, Cpu::txa;
-    0x8B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x8C, InstructionType::STY, AddressingMode::Absolute, 0, false, Cpu::sty;
-    0x8D, InstructionType::STA, AddressingMode::Absolute, 0, false, Cpu::sta;
-    0x8E, InstructionType::STX, AddressingMode::Absolute, 0, false, Cpu::stx;
-    0x8F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x90, InstructionType::BCC, AddressingMode::Relative, 0, false, Cpu::bcc;
-    0x91, InstructionType::STA, AddressingMode::IndirectY, 0, false, Cpu::sta;
-    0x92, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x93, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x94, InstructionType::STY, AddressingMode::ZeroPageX, 0, false, Cpu::sty;
-    0x95, InstructionType::STA, AddressingMode::ZeroPageX, 0, false, Cpu::sta;
-    0x96, InstructionType::STX, AddressingMode::ZeroPageY, 0, false, Cpu::stx;
-    0x97, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x98, InstructionType::TYA, AddressingMode::Implied, 0, false, Cpu::tya;
-    0x99, InstructionType::STA, AddressingMode::AbsoluteY, 0, false, Cpu::sta;
-    0x9A, InstructionType::TXS, AddressingMode::Implied, 0, false, Cpu::txs;
-    0x9B, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x9C, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x9D, InstructionType::STA, AddressingMode::AbsoluteX, 0, false, Cpu::sta;
-    0x9E, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0x9F, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xA0, InstructionType::LDY, AddressingMode::Immediate, 0, false, Cpu::ldy;
-    0xA1, InstructionType::LDA, AddressingMode::IndirectX, 0, false, Cpu::lda;
-    0xA2, InstructionType::LDX, AddressingMode::Immediate, 0, false, Cpu::ldx;
-    0xA3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xA4, InstructionType::LDY, AddressingMode::ZeroPage, 0, false, Cpu::ldy;
-    0xA5, InstructionType::LDA, AddressingMode::ZeroPage, 0, false, Cpu::lda;
-    0xA6, InstructionType::LDX, AddressingMode::ZeroPage, 0, false, Cpu::ldx;
-    0xA7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xA8, InstructionType::TAY, AddressingMode::Implied, 0, false, Cpu::tay;
-    0xA9, InstructionType::LDA, AddressingMode::Immediate, 0, false, Cpu::lda;
-    0xAA, InstructionType::TAX, AddressingMode::Implied, 0, false, Cpu::tax;
-    0xAB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xAC, InstructionType::LDY, AddressingMode::Absolute, 0, false, Cpu::ldy;
-    0xAD, InstructionType::LDA, AddressingMode::Absolute, 0, false, Cpu::lda;
-    0xAE, InstructionType::LDX, AddressingMode::Absolute, 0, false, Cpu::ldx;
-    0xAF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xB0, InstructionType::BCS, AddressingMode::Relative, 0, false, Cpu::bcs;
-    0xB1, InstructionType::LDA, AddressingMode::IndirectY, 0, false, Cpu::lda;
-    0xB2, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xB3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xB4, InstructionType::LDY, AddressingMode::ZeroPageX, 0, false, Cpu::ldy;
-    0xB5, InstructionType::LDA, AddressingMode::ZeroPageX, 0, false, Cpu::lda;
-    0xB6, InstructionType::LDX, AddressingMode::ZeroPageY, 0, false, Cpu::ldx;
-    0xB7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xB8, InstructionType::CLV, AddressingMode::Implied, 0, false, Cpu::clv;
-    0xB9, InstructionType::LDA, AddressingMode::AbsoluteY, 0, false, Cpu::lda;
-    0xBA, InstructionType::TSX, AddressingMode::Implied, 0, false, Cpu::tsx;
-    0xBB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xBC, InstructionType::LDY, AddressingMode::AbsoluteX, 0, false, Cpu::ldy;
-    0xBD, InstructionType::LDA, AddressingMode::AbsoluteX, 0, false, Cpu::lda;
-    0xBE, InstructionType::LDX, AddressingMode::AbsoluteY, 0, false, Cpu::ldx;
-    0xBF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xC0, InstructionType::CPY, AddressingMode::Immediate, 0, false, Cpu::cpy;
-    0xC1, InstructionType::CMP, AddressingMode::IndirectX, 0, false, Cpu::cmp;
-    0xC2, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xC3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xC4, InstructionType::CPY, AddressingMode::ZeroPage, 0, false, Cpu::cpy;
-    0xC5, InstructionType::CMP, AddressingMode::ZeroPage, 0, false, Cpu::cmp;
-    0xC6, InstructionType::DEC, AddressingMode::ZeroPage, 0, false, Cpu::dec;
-    0xC7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xC8, InstructionType::INY, AddressingMode::Implied, 0, false, Cpu::iny;
-    0xC9, InstructionType::CMP, AddressingMode::Immediate, 0, false, Cpu::cmp;
-    0xCA, InstructionType::DEX, AddressingMode::Implied, 0, false, Cpu::dex;
-    0xCB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xCC, InstructionType::CPY, AddressingMode::Absolute, 0, false, Cpu::cpy;
-    0xCD, InstructionType::CMP, AddressingMode::Absolute, 0, false, Cpu::cmp;
-    0xCE, InstructionType::DEC, AddressingMode::Absolute, 0, false, Cpu::dec;
-    0xCF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xD0, InstructionType::BNE, AddressingMode::Relative, 0, false, Cpu::bne;
-    0xD1, InstructionType::CMP, AddressingMode::IndirectY, 0, false, Cpu::cmp;
-    0xD2, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xD3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xD4, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xD5, InstructionType::CMP, AddressingMode::ZeroPageX, 0, false, Cpu::cmp;
-    0xD6, InstructionType::DEC, AddressingMode::ZeroPageX, 0, false, Cpu::dec;
-    0xD7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xD8, InstructionType::CLD, AddressingMode::Implied, 0, false, Cpu::cld;
-    0xD9, InstructionType::CMP, AddressingMode::AbsoluteY, 0, false, Cpu::cmp;
-    0xDA, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xDB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xDC, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xDD, InstructionType::CMP, AddressingMode::AbsoluteX, 0, false, Cpu::cmp;
-    0xDE, InstructionType::DEC, AddressingMode::AbsoluteX, 0, false, Cpu::dec;
-    0xDF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xE0, InstructionType::CPX, AddressingMode::Immediate, 0, false, Cpu::cpx;
-    0xE1, InstructionType::SBC, AddressingMode::IndirectX, 0, false, Cpu::sbc;
-    0xE2, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xE3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xE4, InstructionType::CPX, AddressingMode::ZeroPage, 0, false, Cpu::cpx;
-    0xE5, InstructionType::SBC, AddressingMode::ZeroPage, 0, false, Cpu::sbc;
-    0xE6, InstructionType::INC, AddressingMode::ZeroPage, 0, false, Cpu::inc;
-    0xE7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xE8, InstructionType::INX, AddressingMode::Implied, 0, false, Cpu::inx;
-    0xE9, InstructionType::SBC, AddressingMode::Immediate, 0, false, Cpu::sbc;
-    0xEA, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xEB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xEC, InstructionType::CPX, AddressingMode::Absolute, 0, false, Cpu::cpx;
-    0xED, InstructionType::SBC, AddressingMode::Absolute, 0, false, Cpu::sbc;
-    0xEE, InstructionType::INC, AddressingMode::Absolute, 0, false, Cpu::inc;
-    0xEF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xF0, InstructionType::BEQ, AddressingMode::Relative, 0, false, Cpu::beq;
-    0xF1, InstructionType::SBC, AddressingMode::IndirectY, 0, false, Cpu::sbc;
-    0xF2, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xF3, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xF4, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xF5, InstructionType::SBC, AddressingMode::ZeroPageX, 0, false, Cpu::sbc;
-    0xF6, InstructionType::INC, AddressingMode::ZeroPageX, 0, false, Cpu::inc;
-    0xF7, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xF8, InstructionType::SED, AddressingMode::Implied, 0, false, Cpu::sed;
-    0xF9, InstructionType::SBC, AddressingMode::AbsoluteY, 0, false, Cpu::sbc;
-    0xFA, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xFB, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xFC, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop;
-    0xFD, InstructionType::SBC, AddressingMode::AbsoluteX, 0, false, Cpu::sbc;
-    0xFE, InstructionType::INC, AddressingMode::AbsoluteX, 0, false, Cpu::inc;
-    0xFF, InstructionType::NOP, AddressingMode::Implied, 0, false, Cpu::nop
-};
+pub struct InstructionTable<M: Memory + 'static>(PhantomData<M>);
+
+impl<M: Memory + 'static> InstructionTable<M> {
+    pub const INSTRUCTIONS: &'static [Instruction<M>] = instruction_table! {
+        0x00, InstructionType::BRK, AddressingMode::Implied, 7, false, Cpu::brk;
+        0x01, InstructionType::ORA, AddressingMode::IndirectX, 6, false, Cpu::ora;
+        0x02, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x03, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x04, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x05, InstructionType::ORA, AddressingMode::ZeroPage, 3, false, Cpu::ora;
+        0x06, InstructionType::ASL, AddressingMode::ZeroPage, 5, false, Cpu::asl;
+        0x07, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x08, InstructionType::PHP, AddressingMode::Implied, 3, false, Cpu::php;
+        0x09, InstructionType::ORA, AddressingMode::Immediate, 2, false, Cpu::ora;
+        0x0A, InstructionType::ASL, AddressingMode::Accumulator, 2, false, Cpu::asl;
+        0x0B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x0C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x0D, InstructionType::ORA, AddressingMode::Absolute, 4, false, Cpu::ora;
+        0x0E, InstructionType::ASL, AddressingMode::Absolute, 6, false, Cpu::asl;
+        0x0F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x10, InstructionType::BPL, AddressingMode::Relative, 2, false, Cpu::bpl;
+        0x11, InstructionType::ORA, AddressingMode::IndirectY, 5, true, Cpu::ora;
+        0x12, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x13, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x14, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x15, InstructionType::ORA, AddressingMode::ZeroPageX, 4, false, Cpu::ora;
+        0x16, InstructionType::ASL, AddressingMode::ZeroPageX, 6, false, Cpu::asl;
+        0x17, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x18, InstructionType::CLC, AddressingMode::Implied, 2, false, Cpu::clc;
+        0x19, InstructionType::ORA, AddressingMode::AbsoluteY, 4, true, Cpu::ora;
+        0x1A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x1B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x1C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x1D, InstructionType::ORA, AddressingMode::AbsoluteX, 4, true, Cpu::ora;
+        0x1E, InstructionType::ASL, AddressingMode::AbsoluteX, 7, false, Cpu::asl;
+        0x1F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x20, InstructionType::JSR, AddressingMode::Absolute, 6, false, Cpu::jsr;
+        0x21, InstructionType::AND, AddressingMode::IndirectX, 6, false, Cpu::and;
+        0x22, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x23, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x24, InstructionType::BIT, AddressingMode::ZeroPage, 3, false, Cpu::bit;
+        0x25, InstructionType::AND, AddressingMode::ZeroPage, 3, false, Cpu::and;
+        0x26, InstructionType::ROL, AddressingMode::ZeroPage, 5, false, Cpu::rol;
+        0x27, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x28, InstructionType::PLP, AddressingMode::Implied, 4, false, Cpu::plp;
+        0x29, InstructionType::AND, AddressingMode::Immediate, 2, false, Cpu::and;
+        0x2A, InstructionType::ROL, AddressingMode::Accumulator, 2, false, Cpu::rol;
+        0x2B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x2C, InstructionType::BIT, AddressingMode::Absolute, 4, false, Cpu::bit;
+        0x2D, InstructionType::AND, AddressingMode::Absolute, 4, false, Cpu::and;
+        0x2E, InstructionType::ROL, AddressingMode::Absolute, 6, false, Cpu::rol;
+        0x2F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x30, InstructionType::BMI, AddressingMode::Relative, 2, false, Cpu::bmi;
+        0x31, InstructionType::AND, AddressingMode::IndirectY, 5, true, Cpu::and;
+        0x32, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x33, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x34, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x35, InstructionType::AND, AddressingMode::ZeroPageX, 4, false, Cpu::and;
+        0x36, InstructionType::ROL, AddressingMode::ZeroPageX, 6, false, Cpu::rol;
+        0x37, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x38, InstructionType::SEC, AddressingMode::Implied, 2, false, Cpu::sec;
+        0x39, InstructionType::AND, AddressingMode::AbsoluteY, 4, true, Cpu::and;
+        0x3A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x3B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x3C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x3D, InstructionType::AND, AddressingMode::AbsoluteX, 4, true, Cpu::and;
+        0x3E, InstructionType::ROL, AddressingMode::AbsoluteX, 7, false, Cpu::rol;
+        0x3F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x40, InstructionType::RTI, AddressingMode::Implied, 6, false, Cpu::rti;
+        0x41, InstructionType::EOR, AddressingMode::IndirectX, 6, false, Cpu::eor;
+        0x42, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x43, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x44, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x45, InstructionType::EOR, AddressingMode::ZeroPage, 3, false, Cpu::eor;
+        0x46, InstructionType::LSR, AddressingMode::ZeroPage, 5, false, Cpu::lsr;
+        0x47, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x48, InstructionType::PHA, AddressingMode::Implied, 3, false, Cpu::pha;
+        0x49, InstructionType::EOR, AddressingMode::Immediate, 2, false, Cpu::eor;
+        0x4A, InstructionType::LSR, AddressingMode::Accumulator, 2, false, Cpu::lsr;
+        0x4B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x4C, InstructionType::JMP, AddressingMode::Absolute, 3, false, Cpu::jmp;
+        0x4D, InstructionType::EOR, AddressingMode::Absolute, 4, false, Cpu::eor;
+        0x4E, InstructionType::LSR, AddressingMode::Absolute, 6, false, Cpu::lsr;
+        0x4F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x50, InstructionType::BVC, AddressingMode::Relative, 2, false, Cpu::bvc;
+        0x51, InstructionType::EOR, AddressingMode::IndirectY, 5, true, Cpu::eor;
+        0x52, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x53, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x54, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x55, InstructionType::EOR, AddressingMode::ZeroPageX, 4, false, Cpu::eor;
+        0x56, InstructionType::LSR, AddressingMode::ZeroPageX, 6, false, Cpu::lsr;
+        0x57, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x58, InstructionType::CLI, AddressingMode::Implied, 2, false, Cpu::cli;
+        0x59, InstructionType::EOR, AddressingMode::AbsoluteY, 4, true, Cpu::eor;
+        0x5A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x5B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x5C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x5D, InstructionType::EOR, AddressingMode::AbsoluteX, 4, true, Cpu::eor;
+        0x5E, InstructionType::LSR, AddressingMode::AbsoluteX, 7, false, Cpu::lsr;
+        0x5F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x60, InstructionType::RTS, AddressingMode::Implied, 6, false, Cpu::rts;
+        0x61, InstructionType::ADC, AddressingMode::IndirectX, 6, false, Cpu::adc;
+        0x62, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x63, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x64, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x65, InstructionType::ADC, AddressingMode::ZeroPage, 3, false, Cpu::adc;
+        0x66, InstructionType::ROR, AddressingMode::ZeroPage, 5, false, Cpu::ror;
+        0x67, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x68, InstructionType::PLA, AddressingMode::Implied, 4, false, Cpu::pla;
+        0x69, InstructionType::ADC, AddressingMode::Immediate, 2, false, Cpu::adc;
+        0x6A, InstructionType::ROR, AddressingMode::Accumulator, 2, false, Cpu::ror;
+        0x6B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x6C, InstructionType::JMP, AddressingMode::Indirect, 5, false, Cpu::jmp;
+        0x6D, InstructionType::ADC, AddressingMode::Absolute, 4, false, Cpu::adc;
+        0x6E, InstructionType::ROR, AddressingMode::Absolute, 6, false, Cpu::ror;
+        0x6F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x70, InstructionType::BVS, AddressingMode::Relative, 2, false, Cpu::bvs;
+        0x71, InstructionType::ADC, AddressingMode::IndirectY, 5, true, Cpu::adc;
+        0x72, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x73, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x74, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x75, InstructionType::ADC, AddressingMode::ZeroPageX, 4, false, Cpu::adc;
+        0x76, InstructionType::ROR, AddressingMode::ZeroPageX, 6, false, Cpu::ror;
+        0x77, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x78, InstructionType::SEI, AddressingMode::Implied, 2, false, Cpu::sei;
+        0x79, InstructionType::ADC, AddressingMode::AbsoluteY, 4, true, Cpu::adc;
+        0x7A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x7B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x7C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x7D, InstructionType::ADC, AddressingMode::AbsoluteX, 4, true, Cpu::adc;
+        0x7E, InstructionType::ROR, AddressingMode::AbsoluteX, 7, false, Cpu::ror;
+        0x7F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x80, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x81, InstructionType::STA, AddressingMode::IndirectX, 6, false, Cpu::sta;
+        0x82, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x83, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x84, InstructionType::STY, AddressingMode::ZeroPage, 3, false, Cpu::sty;
+        0x85, InstructionType::STA, AddressingMode::ZeroPage, 3, false, Cpu::sta;
+        0x86, InstructionType::STX, AddressingMode::ZeroPage, 3, false, Cpu::stx;
+        0x87, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x88, InstructionType::DEY, AddressingMode::Implied, 2, false, Cpu::dey;
+        0x89, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x8A, InstructionType::TXA, AddressingMode::Implied, 2, false, Cpu::txa;
+        0x8B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x8C, InstructionType::STY, AddressingMode::Absolute, 4, false, Cpu::sty;
+        0x8D, InstructionType::STA, AddressingMode::Absolute, 4, false, Cpu::sta;
+        0x8E, InstructionType::STX, AddressingMode::Absolute, 4, false, Cpu::stx;
+        0x8F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x90, InstructionType::BCC, AddressingMode::Relative, 2, false, Cpu::bcc;
+        0x91, InstructionType::STA, AddressingMode::IndirectY, 6, false, Cpu::sta;
+        0x92, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x93, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x94, InstructionType::STY, AddressingMode::ZeroPageX, 4, false, Cpu::sty;
+        0x95, InstructionType::STA, AddressingMode::ZeroPageX, 4, false, Cpu::sta;
+        0x96, InstructionType::STX, AddressingMode::ZeroPageY, 4, false, Cpu::stx;
+        0x97, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x98, InstructionType::TYA, AddressingMode::Implied, 2, false, Cpu::tya;
+        0x99, InstructionType::STA, AddressingMode::AbsoluteY, 5, false, Cpu::sta;
+        0x9A, InstructionType::TXS, AddressingMode::Implied, 2, false, Cpu::txs;
+        0x9B, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x9C, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x9D, InstructionType::STA, AddressingMode::AbsoluteX, 5, false, Cpu::sta;
+        0x9E, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x9F, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xA0, InstructionType::LDY, AddressingMode::Immediate, 2, false, Cpu::ldy;
+        0xA1, InstructionType::LDA, AddressingMode::IndirectX, 6, false, Cpu::lda;
+        0xA2, InstructionType::LDX, AddressingMode::Immediate, 2, false, Cpu::ldx;
+        0xA3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xA4, InstructionType::LDY, AddressingMode::ZeroPage, 3, false, Cpu::ldy;
+        0xA5, InstructionType::LDA, AddressingMode::ZeroPage, 3, false, Cpu::lda;
+        0xA6, InstructionType::LDX, AddressingMode::ZeroPage, 3, false, Cpu::ldx;
+        0xA7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xA8, InstructionType::TAY, AddressingMode::Implied, 2, false, Cpu::tay;
+        0xA9, InstructionType::LDA, AddressingMode::Immediate, 2, false, Cpu::lda;
+        0xAA, InstructionType::TAX, AddressingMode::Implied, 2, false, Cpu::tax;
+        0xAB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xAC, InstructionType::LDY, AddressingMode::Absolute, 4, false, Cpu::ldy;
+        0xAD, InstructionType::LDA, AddressingMode::Absolute, 4, false, Cpu::lda;
+        0xAE, InstructionType::LDX, AddressingMode::Absolute, 4, false, Cpu::ldx;
+        0xAF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xB0, InstructionType::BCS, AddressingMode::Relative, 2, false, Cpu::bcs;
+        0xB1, InstructionType::LDA, AddressingMode::IndirectY, 5, true, Cpu::lda;
+        0xB2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xB3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xB4, InstructionType::LDY, AddressingMode::ZeroPageX, 4, false, Cpu::ldy;
+        0xB5, InstructionType::LDA, AddressingMode::ZeroPageX, 4, false, Cpu::lda;
+        0xB6, InstructionType::LDX, AddressingMode::ZeroPageY, 4, false, Cpu::ldx;
+        0xB7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xB8, InstructionType::CLV, AddressingMode::Implied, 2, false, Cpu::clv;
+        0xB9, InstructionType::LDA, AddressingMode::AbsoluteY, 4, true, Cpu::lda;
+        0xBA, InstructionType::TSX, AddressingMode::Implied, 2, false, Cpu::tsx;
+        0xBB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xBC, InstructionType::LDY, AddressingMode::AbsoluteX, 4, true, Cpu::ldy;
+        0xBD, InstructionType::LDA, AddressingMode::AbsoluteX, 4, true, Cpu::lda;
+        0xBE, InstructionType::LDX, AddressingMode::AbsoluteY, 4, true, Cpu::ldx;
+        0xBF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xC0, InstructionType::CPY, AddressingMode::Immediate, 2, false, Cpu::cpy;
+        0xC1, InstructionType::CMP, AddressingMode::IndirectX, 6, false, Cpu::cmp;
+        0xC2, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xC3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xC4, InstructionType::CPY, AddressingMode::ZeroPage, 3, false, Cpu::cpy;
+        0xC5, InstructionType::CMP, AddressingMode::ZeroPage, 3, false, Cpu::cmp;
+        0xC6, InstructionType::DEC, AddressingMode::ZeroPage, 5, false, Cpu::dec;
+        0xC7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xC8, InstructionType::INY, AddressingMode::Implied, 2, false, Cpu::iny;
+        0xC9, InstructionType::CMP, AddressingMode::Immediate, 2, false, Cpu::cmp;
+        0xCA, InstructionType::DEX, AddressingMode::Implied, 2, false, Cpu::dex;
+        0xCB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xCC, InstructionType::CPY, AddressingMode::Absolute, 4, false, Cpu::cpy;
+        0xCD, InstructionType::CMP, AddressingMode::Absolute, 4, false, Cpu::cmp;
+        0xCE, InstructionType::DEC, AddressingMode::Absolute, 6, false, Cpu::dec;
+        0xCF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xD0, InstructionType::BNE, AddressingMode::Relative, 2, false, Cpu::bne;
+        0xD1, InstructionType::CMP, AddressingMode::IndirectY, 5, true, Cpu::cmp;
+        0xD2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xD3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xD4, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xD5, InstructionType::CMP, AddressingMode::ZeroPageX, 4, false, Cpu::cmp;
+        0xD6, InstructionType::DEC, AddressingMode::ZeroPageX, 6, false, Cpu::dec;
+        0xD7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xD8, InstructionType::CLD, AddressingMode::Implied, 2, false, Cpu::cld;
+        0xD9, InstructionType::CMP, AddressingMode::AbsoluteY, 4, true, Cpu::cmp;
+        0xDA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xDB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xDC, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xDD, InstructionType::CMP, AddressingMode::AbsoluteX, 4, true, Cpu::cmp;
+        0xDE, InstructionType::DEC, AddressingMode::AbsoluteX, 7, false, Cpu::dec;
+        0xDF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xE0, InstructionType::CPX, AddressingMode::Immediate, 2, false, Cpu::cpx;
+        0xE1, InstructionType::SBC, AddressingMode::IndirectX, 6, false, Cpu::sbc;
+        0xE2, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xE3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xE4, InstructionType::CPX, AddressingMode::ZeroPage, 3, false, Cpu::cpx;
+        0xE5, InstructionType::SBC, AddressingMode::ZeroPage, 3, false, Cpu::sbc;
+        0xE6, InstructionType::INC, AddressingMode::ZeroPage, 5, false, Cpu::inc;
+        0xE7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xE8, InstructionType::INX, AddressingMode::Implied, 2, false, Cpu::inx;
+        0xE9, InstructionType::SBC, AddressingMode::Immediate, 2, false, Cpu::sbc;
+        0xEA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xEB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xEC, InstructionType::CPX, AddressingMode::Absolute, 4, false, Cpu::cpx;
+        0xED, InstructionType::SBC, AddressingMode::Absolute, 4, false, Cpu::sbc;
+        0xEE, InstructionType::INC, AddressingMode::Absolute, 6, false, Cpu::inc;
+        0xEF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xF0, InstructionType::BEQ, AddressingMode::Relative, 2, false, Cpu::beq;
+        0xF1, InstructionType::SBC, AddressingMode::IndirectY, 5, true, Cpu::sbc;
+        0xF2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xF3, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xF4, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xF5, InstructionType::SBC, AddressingMode::ZeroPageX, 4, false, Cpu::sbc;
+        0xF6, InstructionType::INC, AddressingMode::ZeroPageX, 6, false, Cpu::inc;
+        0xF7, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xF8, InstructionType::SED, AddressingMode::Implied, 2, false, Cpu::sed;
+        0xF9, InstructionType::SBC, AddressingMode::AbsoluteY, 4, true, Cpu::sbc;
+        0xFA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xFB, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xFC, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xFD, InstructionType::SBC, AddressingMode::AbsoluteX, 4, true, Cpu::sbc;
+        0xFE, InstructionType::INC, AddressingMode::AbsoluteX, 7, false, Cpu::inc;
+        0xFF, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop
+    };
+
+    /// The NMOS instruction set including the undocumented opcodes.
+    pub const UNDOCUMENTED_INSTRUCTIONS: &'static [Instruction<M>] = instruction_table! {
+        0x00, InstructionType::BRK, AddressingMode::Implied, 7, false, Cpu::brk;
+        0x01, InstructionType::ORA, AddressingMode::IndirectX, 6, false, Cpu::ora;
+        0x02, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x03, InstructionType::SLO, AddressingMode::IndirectX, 8, false, Cpu::slo;
+        0x04, InstructionType::NOP, AddressingMode::ZeroPage, 3, false, Cpu::nop;
+        0x05, InstructionType::ORA, AddressingMode::ZeroPage, 3, false, Cpu::ora;
+        0x06, InstructionType::ASL, AddressingMode::ZeroPage, 5, false, Cpu::asl;
+        0x07, InstructionType::SLO, AddressingMode::ZeroPage, 5, false, Cpu::slo;
+        0x08, InstructionType::PHP, AddressingMode::Implied, 3, false, Cpu::php;
+        0x09, InstructionType::ORA, AddressingMode::Immediate, 2, false, Cpu::ora;
+        0x0A, InstructionType::ASL, AddressingMode::Accumulator, 2, false, Cpu::asl;
+        0x0B, InstructionType::ANC, AddressingMode::Immediate, 2, false, Cpu::anc;
+        0x0C, InstructionType::NOP, AddressingMode::Absolute, 4, false, Cpu::nop;
+        0x0D, InstructionType::ORA, AddressingMode::Absolute, 4, false, Cpu::ora;
+        0x0E, InstructionType::ASL, AddressingMode::Absolute, 6, false, Cpu::asl;
+        0x0F, InstructionType::SLO, AddressingMode::Absolute, 6, false, Cpu::slo;
+        0x10, InstructionType::BPL, AddressingMode::Relative, 2, false, Cpu::bpl;
+        0x11, InstructionType::ORA, AddressingMode::IndirectY, 5, true, Cpu::ora;
+        0x12, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x13, InstructionType::SLO, AddressingMode::IndirectY, 8, false, Cpu::slo;
+        0x14, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0x15, InstructionType::ORA, AddressingMode::ZeroPageX, 4, false, Cpu::ora;
+        0x16, InstructionType::ASL, AddressingMode::ZeroPageX, 6, false, Cpu::asl;
+        0x17, InstructionType::SLO, AddressingMode::ZeroPageX, 6, false, Cpu::slo;
+        0x18, InstructionType::CLC, AddressingMode::Implied, 2, false, Cpu::clc;
+        0x19, InstructionType::ORA, AddressingMode::AbsoluteY, 4, true, Cpu::ora;
+        0x1A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x1B, InstructionType::SLO, AddressingMode::AbsoluteY, 7, false, Cpu::slo;
+        0x1C, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0x1D, InstructionType::ORA, AddressingMode::AbsoluteX, 4, true, Cpu::ora;
+        0x1E, InstructionType::ASL, AddressingMode::AbsoluteX, 7, false, Cpu::asl;
+        0x1F, InstructionType::SLO, AddressingMode::AbsoluteX, 7, false, Cpu::slo;
+        0x20, InstructionType::JSR, AddressingMode::Absolute, 6, false, Cpu::jsr;
+        0x21, InstructionType::AND, AddressingMode::IndirectX, 6, false, Cpu::and;
+        0x22, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x23, InstructionType::RLA, AddressingMode::IndirectX, 8, false, Cpu::rla;
+        0x24, InstructionType::BIT, AddressingMode::ZeroPage, 3, false, Cpu::bit;
+        0x25, InstructionType::AND, AddressingMode::ZeroPage, 3, false, Cpu::and;
+        0x26, InstructionType::ROL, AddressingMode::ZeroPage, 5, false, Cpu::rol;
+        0x27, InstructionType::RLA, AddressingMode::ZeroPage, 5, false, Cpu::rla;
+        0x28, InstructionType::PLP, AddressingMode::Implied, 4, false, Cpu::plp;
+        0x29, InstructionType::AND, AddressingMode::Immediate, 2, false, Cpu::and;
+        0x2A, InstructionType::ROL, AddressingMode::Accumulator, 2, false, Cpu::rol;
+        0x2B, InstructionType::ANC, AddressingMode::Immediate, 2, false, Cpu::anc;
+        0x2C, InstructionType::BIT, AddressingMode::Absolute, 4, false, Cpu::bit;
+        0x2D, InstructionType::AND, AddressingMode::Absolute, 4, false, Cpu::and;
+        0x2E, InstructionType::ROL, AddressingMode::Absolute, 6, false, Cpu::rol;
+        0x2F, InstructionType::RLA, AddressingMode::Absolute, 6, false, Cpu::rla;
+        0x30, InstructionType::BMI, AddressingMode::Relative, 2, false, Cpu::bmi;
+        0x31, InstructionType::AND, AddressingMode::IndirectY, 5, true, Cpu::and;
+        0x32, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x33, InstructionType::RLA, AddressingMode::IndirectY, 8, false, Cpu::rla;
+        0x34, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0x35, InstructionType::AND, AddressingMode::ZeroPageX, 4, false, Cpu::and;
+        0x36, InstructionType::ROL, AddressingMode::ZeroPageX, 6, false, Cpu::rol;
+        0x37, InstructionType::RLA, AddressingMode::ZeroPageX, 6, false, Cpu::rla;
+        0x38, InstructionType::SEC, AddressingMode::Implied, 2, false, Cpu::sec;
+        0x39, InstructionType::AND, AddressingMode::AbsoluteY, 4, true, Cpu::and;
+        0x3A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x3B, InstructionType::RLA, AddressingMode::AbsoluteY, 7, false, Cpu::rla;
+        0x3C, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0x3D, InstructionType::AND, AddressingMode::AbsoluteX, 4, true, Cpu::and;
+        0x3E, InstructionType::ROL, AddressingMode::AbsoluteX, 7, false, Cpu::rol;
+        0x3F, InstructionType::RLA, AddressingMode::AbsoluteX, 7, false, Cpu::rla;
+        0x40, InstructionType::RTI, AddressingMode::Implied, 6, false, Cpu::rti;
+        0x41, InstructionType::EOR, AddressingMode::IndirectX, 6, false, Cpu::eor;
+        0x42, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x43, InstructionType::SRE, AddressingMode::IndirectX, 8, false, Cpu::sre;
+        0x44, InstructionType::NOP, AddressingMode::ZeroPage, 3, false, Cpu::nop;
+        0x45, InstructionType::EOR, AddressingMode::ZeroPage, 3, false, Cpu::eor;
+        0x46, InstructionType::LSR, AddressingMode::ZeroPage, 5, false, Cpu::lsr;
+        0x47, InstructionType::SRE, AddressingMode::ZeroPage, 5, false, Cpu::sre;
+        0x48, InstructionType::PHA, AddressingMode::Implied, 3, false, Cpu::pha;
+        0x49, InstructionType::EOR, AddressingMode::Immediate, 2, false, Cpu::eor;
+        0x4A, InstructionType::LSR, AddressingMode::Accumulator, 2, false, Cpu::lsr;
+        0x4B, InstructionType::ALR, AddressingMode::Immediate, 2, false, Cpu::alr;
+        0x4C, InstructionType::JMP, AddressingMode::Absolute, 3, false, Cpu::jmp;
+        0x4D, InstructionType::EOR, AddressingMode::Absolute, 4, false, Cpu::eor;
+        0x4E, InstructionType::LSR, AddressingMode::Absolute, 6, false, Cpu::lsr;
+        0x4F, InstructionType::SRE, AddressingMode::Absolute, 6, false, Cpu::sre;
+        0x50, InstructionType::BVC, AddressingMode::Relative, 2, false, Cpu::bvc;
+        0x51, InstructionType::EOR, AddressingMode::IndirectY, 5, true, Cpu::eor;
+        0x52, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x53, InstructionType::SRE, AddressingMode::IndirectY, 8, false, Cpu::sre;
+        0x54, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0x55, InstructionType::EOR, AddressingMode::ZeroPageX, 4, false, Cpu::eor;
+        0x56, InstructionType::LSR, AddressingMode::ZeroPageX, 6, false, Cpu::lsr;
+        0x57, InstructionType::SRE, AddressingMode::ZeroPageX, 6, false, Cpu::sre;
+        0x58, InstructionType::CLI, AddressingMode::Implied, 2, false, Cpu::cli;
+        0x59, InstructionType::EOR, AddressingMode::AbsoluteY, 4, true, Cpu::eor;
+        0x5A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x5B, InstructionType::SRE, AddressingMode::AbsoluteY, 7, false, Cpu::sre;
+        0x5C, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0x5D, InstructionType::EOR, AddressingMode::AbsoluteX, 4, true, Cpu::eor;
+        0x5E, InstructionType::LSR, AddressingMode::AbsoluteX, 7, false, Cpu::lsr;
+        0x5F, InstructionType::SRE, AddressingMode::AbsoluteX, 7, false, Cpu::sre;
+        0x60, InstructionType::RTS, AddressingMode::Implied, 6, false, Cpu::rts;
+        0x61, InstructionType::ADC, AddressingMode::IndirectX, 6, false, Cpu::adc;
+        0x62, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x63, InstructionType::RRA, AddressingMode::IndirectX, 8, false, Cpu::rra;
+        0x64, InstructionType::NOP, AddressingMode::ZeroPage, 3, false, Cpu::nop;
+        0x65, InstructionType::ADC, AddressingMode::ZeroPage, 3, false, Cpu::adc;
+        0x66, InstructionType::ROR, AddressingMode::ZeroPage, 5, false, Cpu::ror;
+        0x67, InstructionType::RRA, AddressingMode::ZeroPage, 5, false, Cpu::rra;
+        0x68, InstructionType::PLA, AddressingMode::Implied, 4, false, Cpu::pla;
+        0x69, InstructionType::ADC, AddressingMode::Immediate, 2, false, Cpu::adc;
+        0x6A, InstructionType::ROR, AddressingMode::Accumulator, 2, false, Cpu::ror;
+        0x6B, InstructionType::ARR, AddressingMode::Immediate, 2, false, Cpu::arr;
+        0x6C, InstructionType::JMP, AddressingMode::Indirect, 5, false, Cpu::jmp;
+        0x6D, InstructionType::ADC, AddressingMode::Absolute, 4, false, Cpu::adc;
+        0x6E, InstructionType::ROR, AddressingMode::Absolute, 6, false, Cpu::ror;
+        0x6F, InstructionType::RRA, AddressingMode::Absolute, 6, false, Cpu::rra;
+        0x70, InstructionType::BVS, AddressingMode::Relative, 2, false, Cpu::bvs;
+        0x71, InstructionType::ADC, AddressingMode::IndirectY, 5, true, Cpu::adc;
+        0x72, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x73, InstructionType::RRA, AddressingMode::IndirectY, 8, false, Cpu::rra;
+        0x74, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0x75, InstructionType::ADC, AddressingMode::ZeroPageX, 4, false, Cpu::adc;
+        0x76, InstructionType::ROR, AddressingMode::ZeroPageX, 6, false, Cpu::ror;
+        0x77, InstructionType::RRA, AddressingMode::ZeroPageX, 6, false, Cpu::rra;
+        0x78, InstructionType::SEI, AddressingMode::Implied, 2, false, Cpu::sei;
+        0x79, InstructionType::ADC, AddressingMode::AbsoluteY, 4, true, Cpu::adc;
+        0x7A, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0x7B, InstructionType::RRA, AddressingMode::AbsoluteY, 7, false, Cpu::rra;
+        0x7C, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0x7D, InstructionType::ADC, AddressingMode::AbsoluteX, 4, true, Cpu::adc;
+        0x7E, InstructionType::ROR, AddressingMode::AbsoluteX, 7, false, Cpu::ror;
+        0x7F, InstructionType::RRA, AddressingMode::AbsoluteX, 7, false, Cpu::rra;
+        0x80, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x81, InstructionType::STA, AddressingMode::IndirectX, 6, false, Cpu::sta;
+        0x82, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x83, InstructionType::SAX, AddressingMode::IndirectX, 6, false, Cpu::sax;
+        0x84, InstructionType::STY, AddressingMode::ZeroPage, 3, false, Cpu::sty;
+        0x85, InstructionType::STA, AddressingMode::ZeroPage, 3, false, Cpu::sta;
+        0x86, InstructionType::STX, AddressingMode::ZeroPage, 3, false, Cpu::stx;
+        0x87, InstructionType::SAX, AddressingMode::ZeroPage, 3, false, Cpu::sax;
+        0x88, InstructionType::DEY, AddressingMode::Implied, 2, false, Cpu::dey;
+        0x89, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x8A, InstructionType::TXA, AddressingMode::Implied, 2, false, Cpu::txa;
+        0x8B, InstructionType::ANE, AddressingMode::Immediate, 2, false, Cpu::ane;
+        0x8C, InstructionType::STY, AddressingMode::Absolute, 4, false, Cpu::sty;
+        0x8D, InstructionType::STA, AddressingMode::Absolute, 4, false, Cpu::sta;
+        0x8E, InstructionType::STX, AddressingMode::Absolute, 4, false, Cpu::stx;
+        0x8F, InstructionType::SAX, AddressingMode::Absolute, 4, false, Cpu::sax;
+        0x90, InstructionType::BCC, AddressingMode::Relative, 2, false, Cpu::bcc;
+        0x91, InstructionType::STA, AddressingMode::IndirectY, 6, false, Cpu::sta;
+        0x92, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0x93, InstructionType::SHA, AddressingMode::IndirectY, 6, false, Cpu::sha;
+        0x94, InstructionType::STY, AddressingMode::ZeroPageX, 4, false, Cpu::sty;
+        0x95, InstructionType::STA, AddressingMode::ZeroPageX, 4, false, Cpu::sta;
+        0x96, InstructionType::STX, AddressingMode::ZeroPageY, 4, false, Cpu::stx;
+        0x97, InstructionType::SAX, AddressingMode::ZeroPageY, 4, false, Cpu::sax;
+        0x98, InstructionType::TYA, AddressingMode::Implied, 2, false, Cpu::tya;
+        0x99, InstructionType::STA, AddressingMode::AbsoluteY, 5, false, Cpu::sta;
+        0x9A, InstructionType::TXS, AddressingMode::Implied, 2, false, Cpu::txs;
+        0x9B, InstructionType::TAS, AddressingMode::AbsoluteY, 5, false, Cpu::tas;
+        0x9C, InstructionType::SHY, AddressingMode::AbsoluteX, 5, false, Cpu::shy;
+        0x9D, InstructionType::STA, AddressingMode::AbsoluteX, 5, false, Cpu::sta;
+        0x9E, InstructionType::SHX, AddressingMode::AbsoluteY, 5, false, Cpu::shx;
+        0x9F, InstructionType::SHA, AddressingMode::AbsoluteY, 5, false, Cpu::sha;
+        0xA0, InstructionType::LDY, AddressingMode::Immediate, 2, false, Cpu::ldy;
+        0xA1, InstructionType::LDA, AddressingMode::IndirectX, 6, false, Cpu::lda;
+        0xA2, InstructionType::LDX, AddressingMode::Immediate, 2, false, Cpu::ldx;
+        0xA3, InstructionType::LAX, AddressingMode::IndirectX, 6, false, Cpu::lax;
+        0xA4, InstructionType::LDY, AddressingMode::ZeroPage, 3, false, Cpu::ldy;
+        0xA5, InstructionType::LDA, AddressingMode::ZeroPage, 3, false, Cpu::lda;
+        0xA6, InstructionType::LDX, AddressingMode::ZeroPage, 3, false, Cpu::ldx;
+        0xA7, InstructionType::LAX, AddressingMode::ZeroPage, 3, false, Cpu::lax;
+        0xA8, InstructionType::TAY, AddressingMode::Implied, 2, false, Cpu::tay;
+        0xA9, InstructionType::LDA, AddressingMode::Immediate, 2, false, Cpu::lda;
+        0xAA, InstructionType::TAX, AddressingMode::Implied, 2, false, Cpu::tax;
+        0xAB, InstructionType::LXA, AddressingMode::Immediate, 2, false, Cpu::lxa;
+        0xAC, InstructionType::LDY, AddressingMode::Absolute, 4, false, Cpu::ldy;
+        0xAD, InstructionType::LDA, AddressingMode::Absolute, 4, false, Cpu::lda;
+        0xAE, InstructionType::LDX, AddressingMode::Absolute, 4, false, Cpu::ldx;
+        0xAF, InstructionType::LAX, AddressingMode::Absolute, 4, false, Cpu::lax;
+        0xB0, InstructionType::BCS, AddressingMode::Relative, 2, false, Cpu::bcs;
+        0xB1, InstructionType::LDA, AddressingMode::IndirectY, 5, true, Cpu::lda;
+        0xB2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xB3, InstructionType::LAX, AddressingMode::IndirectY, 5, true, Cpu::lax;
+        0xB4, InstructionType::LDY, AddressingMode::ZeroPageX, 4, false, Cpu::ldy;
+        0xB5, InstructionType::LDA, AddressingMode::ZeroPageX, 4, false, Cpu::lda;
+        0xB6, InstructionType::LDX, AddressingMode::ZeroPageY, 4, false, Cpu::ldx;
+        0xB7, InstructionType::LAX, AddressingMode::ZeroPageY, 4, false, Cpu::lax;
+        0xB8, InstructionType::CLV, AddressingMode::Implied, 2, false, Cpu::clv;
+        0xB9, InstructionType::LDA, AddressingMode::AbsoluteY, 4, true, Cpu::lda;
+        0xBA, InstructionType::TSX, AddressingMode::Implied, 2, false, Cpu::tsx;
+        0xBB, InstructionType::LAS, AddressingMode::AbsoluteY, 4, true, Cpu::las;
+        0xBC, InstructionType::LDY, AddressingMode::AbsoluteX, 4, true, Cpu::ldy;
+        0xBD, InstructionType::LDA, AddressingMode::AbsoluteX, 4, true, Cpu::lda;
+        0xBE, InstructionType::LDX, AddressingMode::AbsoluteY, 4, true, Cpu::ldx;
+        0xBF, InstructionType::LAX, AddressingMode::AbsoluteY, 4, true, Cpu::lax;
+        0xC0, InstructionType::CPY, AddressingMode::Immediate, 2, false, Cpu::cpy;
+        0xC1, InstructionType::CMP, AddressingMode::IndirectX, 6, false, Cpu::cmp;
+        0xC2, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0xC3, InstructionType::DCP, AddressingMode::IndirectX, 8, false, Cpu::dcp;
+        0xC4, InstructionType::CPY, AddressingMode::ZeroPage, 3, false, Cpu::cpy;
+        0xC5, InstructionType::CMP, AddressingMode::ZeroPage, 3, false, Cpu::cmp;
+        0xC6, InstructionType::DEC, AddressingMode::ZeroPage, 5, false, Cpu::dec;
+        0xC7, InstructionType::DCP, AddressingMode::ZeroPage, 5, false, Cpu::dcp;
+        0xC8, InstructionType::INY, AddressingMode::Implied, 2, false, Cpu::iny;
+        0xC9, InstructionType::CMP, AddressingMode::Immediate, 2, false, Cpu::cmp;
+        0xCA, InstructionType::DEX, AddressingMode::Implied, 2, false, Cpu::dex;
+        0xCB, InstructionType::SBX, AddressingMode::Immediate, 2, false, Cpu::sbx;
+        0xCC, InstructionType::CPY, AddressingMode::Absolute, 4, false, Cpu::cpy;
+        0xCD, InstructionType::CMP, AddressingMode::Absolute, 4, false, Cpu::cmp;
+        0xCE, InstructionType::DEC, AddressingMode::Absolute, 6, false, Cpu::dec;
+        0xCF, InstructionType::DCP, AddressingMode::Absolute, 6, false, Cpu::dcp;
+        0xD0, InstructionType::BNE, AddressingMode::Relative, 2, false, Cpu::bne;
+        0xD1, InstructionType::CMP, AddressingMode::IndirectY, 5, true, Cpu::cmp;
+        0xD2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xD3, InstructionType::DCP, AddressingMode::IndirectY, 8, false, Cpu::dcp;
+        0xD4, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0xD5, InstructionType::CMP, AddressingMode::ZeroPageX, 4, false, Cpu::cmp;
+        0xD6, InstructionType::DEC, AddressingMode::ZeroPageX, 6, false, Cpu::dec;
+        0xD7, InstructionType::DCP, AddressingMode::ZeroPageX, 6, false, Cpu::dcp;
+        0xD8, InstructionType::CLD, AddressingMode::Implied, 2, false, Cpu::cld;
+        0xD9, InstructionType::CMP, AddressingMode::AbsoluteY, 4, true, Cpu::cmp;
+        0xDA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xDB, InstructionType::DCP, AddressingMode::AbsoluteY, 7, false, Cpu::dcp;
+        0xDC, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0xDD, InstructionType::CMP, AddressingMode::AbsoluteX, 4, true, Cpu::cmp;
+        0xDE, InstructionType::DEC, AddressingMode::AbsoluteX, 7, false, Cpu::dec;
+        0xDF, InstructionType::DCP, AddressingMode::AbsoluteX, 7, false, Cpu::dcp;
+        0xE0, InstructionType::CPX, AddressingMode::Immediate, 2, false, Cpu::cpx;
+        0xE1, InstructionType::SBC, AddressingMode::IndirectX, 6, false, Cpu::sbc;
+        0xE2, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0xE3, InstructionType::ISC, AddressingMode::IndirectX, 8, false, Cpu::isc;
+        0xE4, InstructionType::CPX, AddressingMode::ZeroPage, 3, false, Cpu::cpx;
+        0xE5, InstructionType::SBC, AddressingMode::ZeroPage, 3, false, Cpu::sbc;
+        0xE6, InstructionType::INC, AddressingMode::ZeroPage, 5, false, Cpu::inc;
+        0xE7, InstructionType::ISC, AddressingMode::ZeroPage, 5, false, Cpu::isc;
+        0xE8, InstructionType::INX, AddressingMode::Implied, 2, false, Cpu::inx;
+        0xE9, InstructionType::SBC, AddressingMode::Immediate, 2, false, Cpu::sbc;
+        0xEA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xEB, InstructionType::SBC, AddressingMode::Immediate, 2, false, Cpu::sbc;
+        0xEC, InstructionType::CPX, AddressingMode::Absolute, 4, false, Cpu::cpx;
+        0xED, InstructionType::SBC, AddressingMode::Absolute, 4, false, Cpu::sbc;
+        0xEE, InstructionType::INC, AddressingMode::Absolute, 6, false, Cpu::inc;
+        0xEF, InstructionType::ISC, AddressingMode::Absolute, 6, false, Cpu::isc;
+        0xF0, InstructionType::BEQ, AddressingMode::Relative, 2, false, Cpu::beq;
+        0xF1, InstructionType::SBC, AddressingMode::IndirectY, 5, true, Cpu::sbc;
+        0xF2, InstructionType::KIL, AddressingMode::Implied, 2, false, Cpu::kil;
+        0xF3, InstructionType::ISC, AddressingMode::IndirectY, 8, false, Cpu::isc;
+        0xF4, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0xF5, InstructionType::SBC, AddressingMode::ZeroPageX, 4, false, Cpu::sbc;
+        0xF6, InstructionType::INC, AddressingMode::ZeroPageX, 6, false, Cpu::inc;
+        0xF7, InstructionType::ISC, AddressingMode::ZeroPageX, 6, false, Cpu::isc;
+        0xF8, InstructionType::SED, AddressingMode::Implied, 2, false, Cpu::sed;
+        0xF9, InstructionType::SBC, AddressingMode::AbsoluteY, 4, true, Cpu::sbc;
+        0xFA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xFB, InstructionType::ISC, AddressingMode::AbsoluteY, 7, false, Cpu::isc;
+        0xFC, InstructionType::NOP, AddressingMode::AbsoluteX, 4, true, Cpu::nop;
+        0xFD, InstructionType::SBC, AddressingMode::AbsoluteX, 4, true, Cpu::sbc;
+        0xFE, InstructionType::INC, AddressingMode::AbsoluteX, 7, false, Cpu::inc;
+        0xFF, InstructionType::ISC, AddressingMode::AbsoluteX, 7, false, Cpu::isc
+    };
+
+    /// The WDC 65C02 instruction set, including the Rockwell bit instructions.
+    pub const CMOS_INSTRUCTIONS: &'static [Instruction<M>] = instruction_table! {
+        0x00, InstructionType::BRK, AddressingMode::Implied, 7, false, Cpu::brk;
+        0x01, InstructionType::ORA, AddressingMode::IndirectX, 6, false, Cpu::ora;
+        0x02, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x03, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x04, InstructionType::TSB, AddressingMode::ZeroPage, 5, false, Cpu::tsb;
+        0x05, InstructionType::ORA, AddressingMode::ZeroPage, 3, false, Cpu::ora;
+        0x06, InstructionType::ASL, AddressingMode::ZeroPage, 5, false, Cpu::asl;
+        0x07, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x08, InstructionType::PHP, AddressingMode::Implied, 3, false, Cpu::php;
+        0x09, InstructionType::ORA, AddressingMode::Immediate, 2, false, Cpu::ora;
+        0x0A, InstructionType::ASL, AddressingMode::Accumulator, 2, false, Cpu::asl;
+        0x0B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x0C, InstructionType::TSB, AddressingMode::Absolute, 6, false, Cpu::tsb;
+        0x0D, InstructionType::ORA, AddressingMode::Absolute, 4, false, Cpu::ora;
+        0x0E, InstructionType::ASL, AddressingMode::Absolute, 6, false, Cpu::asl;
+        0x0F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x10, InstructionType::BPL, AddressingMode::Relative, 2, false, Cpu::bpl;
+        0x11, InstructionType::ORA, AddressingMode::IndirectY, 5, true, Cpu::ora;
+        0x12, InstructionType::ORA, AddressingMode::ZeroPageIndirect, 5, false, Cpu::ora;
+        0x13, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x14, InstructionType::TRB, AddressingMode::ZeroPage, 5, false, Cpu::trb;
+        0x15, InstructionType::ORA, AddressingMode::ZeroPageX, 4, false, Cpu::ora;
+        0x16, InstructionType::ASL, AddressingMode::ZeroPageX, 6, false, Cpu::asl;
+        0x17, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x18, InstructionType::CLC, AddressingMode::Implied, 2, false, Cpu::clc;
+        0x19, InstructionType::ORA, AddressingMode::AbsoluteY, 4, true, Cpu::ora;
+        0x1A, InstructionType::INC, AddressingMode::Accumulator, 2, false, Cpu::inc;
+        0x1B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x1C, InstructionType::TRB, AddressingMode::Absolute, 6, false, Cpu::trb;
+        0x1D, InstructionType::ORA, AddressingMode::AbsoluteX, 4, true, Cpu::ora;
+        0x1E, InstructionType::ASL, AddressingMode::AbsoluteX, 6, true, Cpu::asl;
+        0x1F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x20, InstructionType::JSR, AddressingMode::Absolute, 6, false, Cpu::jsr;
+        0x21, InstructionType::AND, AddressingMode::IndirectX, 6, false, Cpu::and;
+        0x22, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x23, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x24, InstructionType::BIT, AddressingMode::ZeroPage, 3, false, Cpu::bit;
+        0x25, InstructionType::AND, AddressingMode::ZeroPage, 3, false, Cpu::and;
+        0x26, InstructionType::ROL, AddressingMode::ZeroPage, 5, false, Cpu::rol;
+        0x27, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x28, InstructionType::PLP, AddressingMode::Implied, 4, false, Cpu::plp;
+        0x29, InstructionType::AND, AddressingMode::Immediate, 2, false, Cpu::and;
+        0x2A, InstructionType::ROL, AddressingMode::Accumulator, 2, false, Cpu::rol;
+        0x2B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x2C, InstructionType::BIT, AddressingMode::Absolute, 4, false, Cpu::bit;
+        0x2D, InstructionType::AND, AddressingMode::Absolute, 4, false, Cpu::and;
+        0x2E, InstructionType::ROL, AddressingMode::Absolute, 6, false, Cpu::rol;
+        0x2F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x30, InstructionType::BMI, AddressingMode::Relative, 2, false, Cpu::bmi;
+        0x31, InstructionType::AND, AddressingMode::IndirectY, 5, true, Cpu::and;
+        0x32, InstructionType::AND, AddressingMode::ZeroPageIndirect, 5, false, Cpu::and;
+        0x33, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x34, InstructionType::BIT, AddressingMode::ZeroPageX, 4, false, Cpu::bit;
+        0x35, InstructionType::AND, AddressingMode::ZeroPageX, 4, false, Cpu::and;
+        0x36, InstructionType::ROL, AddressingMode::ZeroPageX, 6, false, Cpu::rol;
+        0x37, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x38, InstructionType::SEC, AddressingMode::Implied, 2, false, Cpu::sec;
+        0x39, InstructionType::AND, AddressingMode::AbsoluteY, 4, true, Cpu::and;
+        0x3A, InstructionType::DEC, AddressingMode::Accumulator, 2, false, Cpu::dec;
+        0x3B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x3C, InstructionType::BIT, AddressingMode::AbsoluteX, 4, true, Cpu::bit;
+        0x3D, InstructionType::AND, AddressingMode::AbsoluteX, 4, true, Cpu::and;
+        0x3E, InstructionType::ROL, AddressingMode::AbsoluteX, 6, true, Cpu::rol;
+        0x3F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x40, InstructionType::RTI, AddressingMode::Implied, 6, false, Cpu::rti;
+        0x41, InstructionType::EOR, AddressingMode::IndirectX, 6, false, Cpu::eor;
+        0x42, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x43, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x44, InstructionType::NOP, AddressingMode::ZeroPage, 3, false, Cpu::nop;
+        0x45, InstructionType::EOR, AddressingMode::ZeroPage, 3, false, Cpu::eor;
+        0x46, InstructionType::LSR, AddressingMode::ZeroPage, 5, false, Cpu::lsr;
+        0x47, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x48, InstructionType::PHA, AddressingMode::Implied, 3, false, Cpu::pha;
+        0x49, InstructionType::EOR, AddressingMode::Immediate, 2, false, Cpu::eor;
+        0x4A, InstructionType::LSR, AddressingMode::Accumulator, 2, false, Cpu::lsr;
+        0x4B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x4C, InstructionType::JMP, AddressingMode::Absolute, 3, false, Cpu::jmp;
+        0x4D, InstructionType::EOR, AddressingMode::Absolute, 4, false, Cpu::eor;
+        0x4E, InstructionType::LSR, AddressingMode::Absolute, 6, false, Cpu::lsr;
+        0x4F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x50, InstructionType::BVC, AddressingMode::Relative, 2, false, Cpu::bvc;
+        0x51, InstructionType::EOR, AddressingMode::IndirectY, 5, true, Cpu::eor;
+        0x52, InstructionType::EOR, AddressingMode::ZeroPageIndirect, 5, false, Cpu::eor;
+        0x53, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x54, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0x55, InstructionType::EOR, AddressingMode::ZeroPageX, 4, false, Cpu::eor;
+        0x56, InstructionType::LSR, AddressingMode::ZeroPageX, 6, false, Cpu::lsr;
+        0x57, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x58, InstructionType::CLI, AddressingMode::Implied, 2, false, Cpu::cli;
+        0x59, InstructionType::EOR, AddressingMode::AbsoluteY, 4, true, Cpu::eor;
+        0x5A, InstructionType::PHY, AddressingMode::Implied, 3, false, Cpu::phy;
+        0x5B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x5C, InstructionType::NOP, AddressingMode::Absolute, 8, false, Cpu::nop;
+        0x5D, InstructionType::EOR, AddressingMode::AbsoluteX, 4, true, Cpu::eor;
+        0x5E, InstructionType::LSR, AddressingMode::AbsoluteX, 6, true, Cpu::lsr;
+        0x5F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x60, InstructionType::RTS, AddressingMode::Implied, 6, false, Cpu::rts;
+        0x61, InstructionType::ADC, AddressingMode::IndirectX, 6, false, Cpu::adc;
+        0x62, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x63, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x64, InstructionType::STZ, AddressingMode::ZeroPage, 3, false, Cpu::stz;
+        0x65, InstructionType::ADC, AddressingMode::ZeroPage, 3, false, Cpu::adc;
+        0x66, InstructionType::ROR, AddressingMode::ZeroPage, 5, false, Cpu::ror;
+        0x67, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x68, InstructionType::PLA, AddressingMode::Implied, 4, false, Cpu::pla;
+        0x69, InstructionType::ADC, AddressingMode::Immediate, 2, false, Cpu::adc;
+        0x6A, InstructionType::ROR, AddressingMode::Accumulator, 2, false, Cpu::ror;
+        0x6B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x6C, InstructionType::JMP, AddressingMode::Indirect, 6, false, Cpu::jmp;
+        0x6D, InstructionType::ADC, AddressingMode::Absolute, 4, false, Cpu::adc;
+        0x6E, InstructionType::ROR, AddressingMode::Absolute, 6, false, Cpu::ror;
+        0x6F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x70, InstructionType::BVS, AddressingMode::Relative, 2, false, Cpu::bvs;
+        0x71, InstructionType::ADC, AddressingMode::IndirectY, 5, true, Cpu::adc;
+        0x72, InstructionType::ADC, AddressingMode::ZeroPageIndirect, 5, false, Cpu::adc;
+        0x73, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x74, InstructionType::STZ, AddressingMode::ZeroPageX, 4, false, Cpu::stz;
+        0x75, InstructionType::ADC, AddressingMode::ZeroPageX, 4, false, Cpu::adc;
+        0x76, InstructionType::ROR, AddressingMode::ZeroPageX, 6, false, Cpu::ror;
+        0x77, InstructionType::RMB, AddressingMode::ZeroPage, 5, false, Cpu::rmb;
+        0x78, InstructionType::SEI, AddressingMode::Implied, 2, false, Cpu::sei;
+        0x79, InstructionType::ADC, AddressingMode::AbsoluteY, 4, true, Cpu::adc;
+        0x7A, InstructionType::PLY, AddressingMode::Implied, 4, false, Cpu::ply;
+        0x7B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x7C, InstructionType::JMP, AddressingMode::AbsoluteIndexedIndirect, 6, false, Cpu::jmp;
+        0x7D, InstructionType::ADC, AddressingMode::AbsoluteX, 4, true, Cpu::adc;
+        0x7E, InstructionType::ROR, AddressingMode::AbsoluteX, 6, true, Cpu::ror;
+        0x7F, InstructionType::BBR, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbr;
+        0x80, InstructionType::BRA, AddressingMode::Relative, 2, false, Cpu::bra;
+        0x81, InstructionType::STA, AddressingMode::IndirectX, 6, false, Cpu::sta;
+        0x82, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0x83, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x84, InstructionType::STY, AddressingMode::ZeroPage, 3, false, Cpu::sty;
+        0x85, InstructionType::STA, AddressingMode::ZeroPage, 3, false, Cpu::sta;
+        0x86, InstructionType::STX, AddressingMode::ZeroPage, 3, false, Cpu::stx;
+        0x87, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0x88, InstructionType::DEY, AddressingMode::Implied, 2, false, Cpu::dey;
+        0x89, InstructionType::BIT, AddressingMode::Immediate, 2, false, Cpu::bit;
+        0x8A, InstructionType::TXA, AddressingMode::Implied, 2, false, Cpu::txa;
+        0x8B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x8C, InstructionType::STY, AddressingMode::Absolute, 4, false, Cpu::sty;
+        0x8D, InstructionType::STA, AddressingMode::Absolute, 4, false, Cpu::sta;
+        0x8E, InstructionType::STX, AddressingMode::Absolute, 4, false, Cpu::stx;
+        0x8F, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0x90, InstructionType::BCC, AddressingMode::Relative, 2, false, Cpu::bcc;
+        0x91, InstructionType::STA, AddressingMode::IndirectY, 6, false, Cpu::sta;
+        0x92, InstructionType::STA, AddressingMode::ZeroPageIndirect, 5, false, Cpu::sta;
+        0x93, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x94, InstructionType::STY, AddressingMode::ZeroPageX, 4, false, Cpu::sty;
+        0x95, InstructionType::STA, AddressingMode::ZeroPageX, 4, false, Cpu::sta;
+        0x96, InstructionType::STX, AddressingMode::ZeroPageY, 4, false, Cpu::stx;
+        0x97, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0x98, InstructionType::TYA, AddressingMode::Implied, 2, false, Cpu::tya;
+        0x99, InstructionType::STA, AddressingMode::AbsoluteY, 5, false, Cpu::sta;
+        0x9A, InstructionType::TXS, AddressingMode::Implied, 2, false, Cpu::txs;
+        0x9B, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0x9C, InstructionType::STZ, AddressingMode::Absolute, 4, false, Cpu::stz;
+        0x9D, InstructionType::STA, AddressingMode::AbsoluteX, 5, false, Cpu::sta;
+        0x9E, InstructionType::STZ, AddressingMode::AbsoluteX, 5, false, Cpu::stz;
+        0x9F, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xA0, InstructionType::LDY, AddressingMode::Immediate, 2, false, Cpu::ldy;
+        0xA1, InstructionType::LDA, AddressingMode::IndirectX, 6, false, Cpu::lda;
+        0xA2, InstructionType::LDX, AddressingMode::Immediate, 2, false, Cpu::ldx;
+        0xA3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xA4, InstructionType::LDY, AddressingMode::ZeroPage, 3, false, Cpu::ldy;
+        0xA5, InstructionType::LDA, AddressingMode::ZeroPage, 3, false, Cpu::lda;
+        0xA6, InstructionType::LDX, AddressingMode::ZeroPage, 3, false, Cpu::ldx;
+        0xA7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xA8, InstructionType::TAY, AddressingMode::Implied, 2, false, Cpu::tay;
+        0xA9, InstructionType::LDA, AddressingMode::Immediate, 2, false, Cpu::lda;
+        0xAA, InstructionType::TAX, AddressingMode::Implied, 2, false, Cpu::tax;
+        0xAB, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xAC, InstructionType::LDY, AddressingMode::Absolute, 4, false, Cpu::ldy;
+        0xAD, InstructionType::LDA, AddressingMode::Absolute, 4, false, Cpu::lda;
+        0xAE, InstructionType::LDX, AddressingMode::Absolute, 4, false, Cpu::ldx;
+        0xAF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xB0, InstructionType::BCS, AddressingMode::Relative, 2, false, Cpu::bcs;
+        0xB1, InstructionType::LDA, AddressingMode::IndirectY, 5, true, Cpu::lda;
+        0xB2, InstructionType::LDA, AddressingMode::ZeroPageIndirect, 5, false, Cpu::lda;
+        0xB3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xB4, InstructionType::LDY, AddressingMode::ZeroPageX, 4, false, Cpu::ldy;
+        0xB5, InstructionType::LDA, AddressingMode::ZeroPageX, 4, false, Cpu::lda;
+        0xB6, InstructionType::LDX, AddressingMode::ZeroPageY, 4, false, Cpu::ldx;
+        0xB7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xB8, InstructionType::CLV, AddressingMode::Implied, 2, false, Cpu::clv;
+        0xB9, InstructionType::LDA, AddressingMode::AbsoluteY, 4, true, Cpu::lda;
+        0xBA, InstructionType::TSX, AddressingMode::Implied, 2, false, Cpu::tsx;
+        0xBB, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xBC, InstructionType::LDY, AddressingMode::AbsoluteX, 4, true, Cpu::ldy;
+        0xBD, InstructionType::LDA, AddressingMode::AbsoluteX, 4, true, Cpu::lda;
+        0xBE, InstructionType::LDX, AddressingMode::AbsoluteY, 4, true, Cpu::ldx;
+        0xBF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xC0, InstructionType::CPY, AddressingMode::Immediate, 2, false, Cpu::cpy;
+        0xC1, InstructionType::CMP, AddressingMode::IndirectX, 6, false, Cpu::cmp;
+        0xC2, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0xC3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xC4, InstructionType::CPY, AddressingMode::ZeroPage, 3, false, Cpu::cpy;
+        0xC5, InstructionType::CMP, AddressingMode::ZeroPage, 3, false, Cpu::cmp;
+        0xC6, InstructionType::DEC, AddressingMode::ZeroPage, 5, false, Cpu::dec;
+        0xC7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xC8, InstructionType::INY, AddressingMode::Implied, 2, false, Cpu::iny;
+        0xC9, InstructionType::CMP, AddressingMode::Immediate, 2, false, Cpu::cmp;
+        0xCA, InstructionType::DEX, AddressingMode::Implied, 2, false, Cpu::dex;
+        0xCB, InstructionType::WAI, AddressingMode::Implied, 3, false, Cpu::wai;
+        0xCC, InstructionType::CPY, AddressingMode::Absolute, 4, false, Cpu::cpy;
+        0xCD, InstructionType::CMP, AddressingMode::Absolute, 4, false, Cpu::cmp;
+        0xCE, InstructionType::DEC, AddressingMode::Absolute, 6, false, Cpu::dec;
+        0xCF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xD0, InstructionType::BNE, AddressingMode::Relative, 2, false, Cpu::bne;
+        0xD1, InstructionType::CMP, AddressingMode::IndirectY, 5, true, Cpu::cmp;
+        0xD2, InstructionType::CMP, AddressingMode::ZeroPageIndirect, 5, false, Cpu::cmp;
+        0xD3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xD4, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0xD5, InstructionType::CMP, AddressingMode::ZeroPageX, 4, false, Cpu::cmp;
+        0xD6, InstructionType::DEC, AddressingMode::ZeroPageX, 6, false, Cpu::dec;
+        0xD7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xD8, InstructionType::CLD, AddressingMode::Implied, 2, false, Cpu::cld;
+        0xD9, InstructionType::CMP, AddressingMode::AbsoluteY, 4, true, Cpu::cmp;
+        0xDA, InstructionType::PHX, AddressingMode::Implied, 3, false, Cpu::phx;
+        0xDB, InstructionType::STP, AddressingMode::Implied, 3, false, Cpu::stp;
+        0xDC, InstructionType::NOP, AddressingMode::Absolute, 4, false, Cpu::nop;
+        0xDD, InstructionType::CMP, AddressingMode::AbsoluteX, 4, true, Cpu::cmp;
+        0xDE, InstructionType::DEC, AddressingMode::AbsoluteX, 7, false, Cpu::dec;
+        0xDF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xE0, InstructionType::CPX, AddressingMode::Immediate, 2, false, Cpu::cpx;
+        0xE1, InstructionType::SBC, AddressingMode::IndirectX, 6, false, Cpu::sbc;
+        0xE2, InstructionType::NOP, AddressingMode::Immediate, 2, false, Cpu::nop;
+        0xE3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xE4, InstructionType::CPX, AddressingMode::ZeroPage, 3, false, Cpu::cpx;
+        0xE5, InstructionType::SBC, AddressingMode::ZeroPage, 3, false, Cpu::sbc;
+        0xE6, InstructionType::INC, AddressingMode::ZeroPage, 5, false, Cpu::inc;
+        0xE7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xE8, InstructionType::INX, AddressingMode::Implied, 2, false, Cpu::inx;
+        0xE9, InstructionType::SBC, AddressingMode::Immediate, 2, false, Cpu::sbc;
+        0xEA, InstructionType::NOP, AddressingMode::Implied, 2, false, Cpu::nop;
+        0xEB, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xEC, InstructionType::CPX, AddressingMode::Absolute, 4, false, Cpu::cpx;
+        0xED, InstructionType::SBC, AddressingMode::Absolute, 4, false, Cpu::sbc;
+        0xEE, InstructionType::INC, AddressingMode::Absolute, 6, false, Cpu::inc;
+        0xEF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs;
+        0xF0, InstructionType::BEQ, AddressingMode::Relative, 2, false, Cpu::beq;
+        0xF1, InstructionType::SBC, AddressingMode::IndirectY, 5, true, Cpu::sbc;
+        0xF2, InstructionType::SBC, AddressingMode::ZeroPageIndirect, 5, false, Cpu::sbc;
+        0xF3, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xF4, InstructionType::NOP, AddressingMode::ZeroPageX, 4, false, Cpu::nop;
+        0xF5, InstructionType::SBC, AddressingMode::ZeroPageX, 4, false, Cpu::sbc;
+        0xF6, InstructionType::INC, AddressingMode::ZeroPageX, 6, false, Cpu::inc;
+        0xF7, InstructionType::SMB, AddressingMode::ZeroPage, 5, false, Cpu::smb;
+        0xF8, InstructionType::SED, AddressingMode::Implied, 2, false, Cpu::sed;
+        0xF9, InstructionType::SBC, AddressingMode::AbsoluteY, 4, true, Cpu::sbc;
+        0xFA, InstructionType::PLX, AddressingMode::Implied, 4, false, Cpu::plx;
+        0xFB, InstructionType::NOP, AddressingMode::Implied, 1, false, Cpu::nop;
+        0xFC, InstructionType::NOP, AddressingMode::Absolute, 4, false, Cpu::nop;
+        0xFD, InstructionType::SBC, AddressingMode::AbsoluteX, 4, true, Cpu::sbc;
+        0xFE, InstructionType::INC, AddressingMode::AbsoluteX, 7, false, Cpu::inc;
+        0xFF, InstructionType::BBS, AddressingMode::ZeroPageRelative, 5, false, Cpu::bbs
+    };
+}
+
+impl<M: Memory + 'static> InstructionTable<M> {
+    /// The table a variant decodes opcodes with. The undocumented opcodes only
+    /// exist on the NMOS parts.
+    pub fn for_variant(variant: Variant, undocumented_opcodes: bool) -> &'static [Instruction<M>] {
+        match variant {
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 if undocumented_opcodes => {
+                Self::UNDOCUMENTED_INSTRUCTIONS
+            }
+            Variant::Nmos6502 | Variant::Ricoh2A03 | Variant::Mos6510 => Self::INSTRUCTIONS,
+            Variant::Cmos65C02 => Self::CMOS_INSTRUCTIONS,
+        }
+    }
+}
@@ -1,7 +1,16 @@
+// Lets the code generated by `rs_6502_macros` refer to `::rs_6502` within tests of this crate
+extern crate self as rs_6502;
+
+pub mod asm;
+pub mod banked_memory;
+pub mod bus;
 pub mod cpu;
 pub mod default_memory;
-mod instruction;
+pub mod disasm;
+pub mod expression;
+pub mod instruction;
 mod instruction_table;
 pub mod memory;
 mod registers;
+pub mod sim65;
 mod util;
@@ -1,13 +1,81 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
 use anyhow::Result;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryError {
+    UnmappedRead(u16),
+    RomWrite { address: u16, value: u8 },
+    Device { address: u16, message: String }, // A mapped device failed the access
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::UnmappedRead(address) => {
+                write!(f, "Read from unmapped address 0x{address:04X}")
+            }
+            MemoryError::RomWrite { address, value } => {
+                write!(f, "Write of 0x{value:02X} to ROM at 0x{address:04X}")
+            }
+            MemoryError::Device { address, message } => {
+                write!(f, "Device at 0x{address:04X} failed: {message}")
+            }
+        }
+    }
+}
+
+impl Error for MemoryError {}
+
+/// Decides what happens when a program writes to a read-only region.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RomWritePolicy {
+    Ignore,
+    Warn, // Ignores the write but logs it
+    Error,
+    Allow, // Writes to ROM like to RAM
+}
+
+impl RomWritePolicy {
+    /// Returns whether the write should be performed, or the error to record
+    /// in its place.
+    pub(crate) fn handle(&self, address: u16, value: u8) -> Result<bool, MemoryError> {
+        match self {
+            RomWritePolicy::Ignore => Ok(false),
+            RomWritePolicy::Warn => {
+                log::warn!("Ignoring write of 0x{value:02X} to ROM at 0x{address:04X}");
+                Ok(false)
+            }
+            RomWritePolicy::Error => Err(MemoryError::RomWrite { address, value }),
+            RomWritePolicy::Allow => Ok(true),
+        }
+    }
+}
+
 pub trait Memory {
     fn new() -> Self;
     fn read_byte(&self, address: u16) -> u8;
     fn read_short(&self, address: u16) -> u16;
 
+    /// Reads a byte for debugging without side effects, devices do not see
+    /// the access and no error is raised.
+    fn peek(&self, address: u16) -> u8 {
+        self.read_byte(address)
+    }
+
     fn write_byte(&mut self, address: u16, value: u8);
     fn write_short(&mut self, address: u16, value: u16);
 
     fn load_from_file(&mut self, name: &str, address: u16) -> Result<()>;
     fn load(&mut self, executable: &[u8], address: u16) -> Result<()>;
+
+    /// Returns and clears the error raised by a previous access, if any.
+    fn take_error(&mut self) -> Option<MemoryError> {
+        None
+    }
+
+    /// Called by a 6510 whenever the levels of its I/O port pins change, so
+    /// that banking can follow the port.
+    fn io_port_changed(&mut self, _pins: u8) {}
 }
@@ -5,7 +5,7 @@ use indent::indent_all_by;
 
 use crate::util::{get_bit, set_bit, toggle_bit};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Flag {
     Negative = 7,
     Overflow = 6,
@@ -53,7 +53,7 @@ impl Display for Flags {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for i in (0_u8..=7).rev() {
             if let Ok(flag) = Flag::try_from(i) {
-                write!(f, "{:<16} = {}\n", flag.to_string(), self.get(flag))?;
+                writeln!(f, "{:<16} = {}", flag.to_string(), self.get(flag))?;
             }
         }
         Ok(())
@@ -72,15 +72,15 @@ impl Flags {
     }
 
     pub fn set(&mut self, flag: Flag, value: bool) {
-        set_bit(self.0, flag as u8, value);
+        self.0 = set_bit(self.0, flag as u8, value);
     }
 
     pub fn toggle(&mut self, flag: Flag) {
-        toggle_bit(self.0, flag as u8);
+        self.0 = toggle_bit(self.0, flag as u8);
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Registers {
     pub x: u8,   // X Index Register
     pub y: u8,   // Y Index Register
@@ -92,14 +92,14 @@ pub struct Registers {
 
 impl Display for Registers {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Registers:\n")?;
-        write!(f, "    X   = 0x{0:02X}   = {0} \n", self.x)?;
-        write!(f, "    Y   = 0x{0:02X}   = {0}\n", self.y)?;
-        write!(f, "    PC  = 0x{0:04X} = {0}\n", self.pc)?;
-        write!(f, "    SP  = 0x{0:02X} = {0}\n", self.sp)?;
-        write!(f, "    ACC = 0x{0:02X}   = {0}\n\n", self.a)?;
-
-        write!(f, "Flags:\n")?;
+        writeln!(f, "Registers:")?;
+        writeln!(f, "    X   = 0x{0:02X}   = {0} ", self.x)?;
+        writeln!(f, "    Y   = 0x{0:02X}   = {0}", self.y)?;
+        writeln!(f, "    PC  = 0x{0:04X} = {0}", self.pc)?;
+        writeln!(f, "    SP  = 0x{0:02X} = {0}", self.sp)?;
+        writeln!(f, "    ACC = 0x{0:02X}   = {0}\n", self.a)?;
+
+        writeln!(f, "Flags:")?;
         write!(f, "{}", indent_all_by(4, self.flags.to_string()))?;
 
         Ok(())
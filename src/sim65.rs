@@ -0,0 +1,410 @@
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+
+use crate::cpu::{Cpu, HookAction, Variant};
+use crate::default_memory::DefaultMemory;
+use crate::memory::Memory;
+
+/// The first of the paravirtualization entry points open, close, read,
+/// write, args and exit, which the cc65 sim65 library calls with JSR.
+pub const PARAVIRT_BASE: u16 = 0xFFF4;
+
+/// The exit code of sim65 when the program could not be run.
+pub const ERROR_EXIT_CODE: i32 = 0x7F;
+
+/// The exit code of sim65 when the program ran out of cycles.
+pub const TIMEOUT_EXIT_CODE: i32 = 0x7E;
+
+// Flags of the cc65 `open`
+const O_ACCESS_MASK: u16 = 0x03;
+const O_READ: u16 = 0x01;
+const O_WRITE: u16 = 0x02;
+const O_CREATE: u16 = 0x10;
+const O_TRUNCATE: u16 = 0x20;
+const O_APPEND: u16 = 0x40;
+const O_EXCLUSIVE: u16 = 0x80;
+
+// The first descriptor given to opened files, the ones below are stdio
+const FIRST_FILE_DESCRIPTOR: usize = 3;
+
+/// The header in front of programs linked for sim65.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Header {
+    pub variant: Variant,
+    pub undocumented_opcodes: bool,
+    pub sp_address: u8, // Zero page location of the C stack pointer
+    pub load_address: u16,
+    pub reset_address: u16,
+}
+
+impl Header {
+    pub const SIZE: usize = 12;
+
+    pub fn parse(binary: &[u8]) -> Result<Header> {
+        let header = binary
+            .get(..Header::SIZE)
+            .ok_or_else(|| anyhow!("Binary is too short for a sim65 header"))?;
+
+        if &header[..5] != b"sim65" {
+            return Err(anyhow!("Binary is not a sim65 program"));
+        }
+
+        if header[5] != 2 {
+            return Err(anyhow!("Unsupported sim65 header version {}", header[5]));
+        }
+
+        let (variant, undocumented_opcodes) = match header[6] {
+            0 => (Variant::Nmos6502, false),
+            1 => (Variant::Cmos65C02, false),
+            2 => (Variant::Nmos6502, true),
+            cpu => return Err(anyhow!("Unknown sim65 cpu type {cpu}")),
+        };
+
+        Ok(Header {
+            variant,
+            undocumented_opcodes,
+            sp_address: header[7],
+            load_address: u16::from_le_bytes([header[8], header[9]]),
+            reset_address: u16::from_le_bytes([header[10], header[11]]),
+        })
+    }
+}
+
+/// Sets up a cpu for a sim65 program like sim65 does and installs the
+/// paravirtualization hooks. `args` are passed to `main`, starting with the
+/// program name. A run ends with `StopReason::Exited` once the program exits.
+pub fn load(binary: &[u8], args: Vec<String>) -> Result<Cpu> {
+    let header = Header::parse(binary)?;
+
+    let mut memory = DefaultMemory::new();
+    memory.load(&binary[Header::SIZE..], header.load_address)?;
+
+    let mut cpu = Cpu::with_variant(memory, header.variant);
+    cpu.set_undocumented_opcodes(header.undocumented_opcodes);
+
+    let paravirt = Rc::new(RefCell::new(ParaVirt {
+        sp_address: header.sp_address,
+        args,
+        files: Vec::new(),
+    }));
+
+    let functions: [fn(&mut ParaVirt, &mut Cpu) -> HookAction; 6] = [
+        ParaVirt::open,
+        ParaVirt::close,
+        ParaVirt::read,
+        ParaVirt::write,
+        ParaVirt::args,
+        ParaVirt::exit,
+    ];
+
+    // Each entry point holds an RTS that returns to the caller once the hook
+    // has serviced the call
+    for (offset, function) in functions.into_iter().enumerate() {
+        let address = PARAVIRT_BASE + offset as u16;
+        let paravirt = Rc::clone(&paravirt);
+
+        cpu.memory.write_byte(address, 0x60);
+        cpu.add_address_hook(address, move |cpu| {
+            function(&mut paravirt.borrow_mut(), cpu)
+        });
+    }
+
+    cpu.memory.write_short(0xFFFC, header.reset_address);
+    cpu.init_registers();
+
+    Ok(cpu)
+}
+
+// The last argument of a call is passed in A and X, the others on the C
+// stack. Results are returned in A and X, failures as -1.
+struct ParaVirt {
+    sp_address: u8,
+    args: Vec<String>,
+    files: Vec<Option<File>>, // Indexed by descriptor from FIRST_FILE_DESCRIPTOR
+}
+
+impl ParaVirt {
+    fn pop_param(&self, cpu: &mut Cpu, increment: u16) -> u16 {
+        let sp = cpu.memory.read_short(self.sp_address as u16);
+        let value = cpu.memory.read_short(sp);
+
+        cpu.memory.write_short(self.sp_address as u16, sp.wrapping_add(increment));
+
+        value
+    }
+
+    fn get_ax(cpu: &Cpu) -> u16 {
+        u16::from_le_bytes([cpu.registers.a, cpu.registers.x])
+    }
+
+    fn set_ax(cpu: &mut Cpu, value: u16) {
+        [cpu.registers.a, cpu.registers.x] = value.to_le_bytes();
+    }
+
+    fn set_result(cpu: &mut Cpu, result: io::Result<usize>) {
+        ParaVirt::set_ax(cpu, result.map_or(0xFFFF, |value| value as u16));
+    }
+
+    fn file(&mut self, descriptor: u16) -> io::Result<&mut File> {
+        let index = (descriptor as usize)
+            .checked_sub(FIRST_FILE_DESCRIPTOR)
+            .ok_or(io::ErrorKind::InvalidInput)?;
+
+        match self.files.get_mut(index) {
+            Some(Some(file)) => Ok(file),
+            _ => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    // int open(const char* name, int flags, ...), Y holds the size of the
+    // arguments, which include the mode if one was given
+    fn open(&mut self, cpu: &mut Cpu) -> HookAction {
+        let _mode = self.pop_param(cpu, (cpu.registers.y as u16).wrapping_sub(4));
+        let flags = self.pop_param(cpu, 2);
+        let name = self.pop_param(cpu, 2);
+
+        let mut options = OpenOptions::new();
+        options
+            .read(flags & O_ACCESS_MASK != O_WRITE)
+            .write(flags & O_ACCESS_MASK != O_READ)
+            .create(flags & O_CREATE != 0)
+            .truncate(flags & O_TRUNCATE != 0)
+            .append(flags & O_APPEND != 0)
+            .create_new(flags & O_CREATE != 0 && flags & O_EXCLUSIVE != 0);
+
+        let result = options.open(read_string(cpu, name)).map(|file| {
+            let free = self.files.iter().position(Option::is_none);
+            let index = free.unwrap_or(self.files.len());
+
+            match free {
+                Some(_) => self.files[index] = Some(file),
+                None => self.files.push(Some(file)),
+            }
+
+            index + FIRST_FILE_DESCRIPTOR
+        });
+
+        ParaVirt::set_result(cpu, result);
+
+        HookAction::Continue
+    }
+
+    // int close(int fd)
+    fn close(&mut self, cpu: &mut Cpu) -> HookAction {
+        let descriptor = ParaVirt::get_ax(cpu);
+
+        let result = self.file(descriptor).map(|_| 0);
+
+        if result.is_ok() {
+            self.files[descriptor as usize - FIRST_FILE_DESCRIPTOR] = None;
+        }
+
+        ParaVirt::set_result(cpu, result);
+
+        HookAction::Continue
+    }
+
+    // int read(int fd, void* buf, unsigned count)
+    fn read(&mut self, cpu: &mut Cpu) -> HookAction {
+        let count = ParaVirt::get_ax(cpu);
+        let buffer = self.pop_param(cpu, 2);
+        let descriptor = self.pop_param(cpu, 2);
+
+        let mut data = vec![0; count as usize];
+
+        let result = match descriptor {
+            0 => io::stdin().read(&mut data),
+            _ => self.file(descriptor).and_then(|file| file.read(&mut data)),
+        };
+
+        if let Ok(length) = result {
+            for (offset, &byte) in data[..length].iter().enumerate() {
+                cpu.memory.write_byte(buffer.wrapping_add(offset as u16), byte);
+            }
+        }
+
+        ParaVirt::set_result(cpu, result);
+
+        HookAction::Continue
+    }
+
+    // int write(int fd, const void* buf, unsigned count)
+    fn write(&mut self, cpu: &mut Cpu) -> HookAction {
+        let count = ParaVirt::get_ax(cpu);
+        let buffer = self.pop_param(cpu, 2);
+        let descriptor = self.pop_param(cpu, 2);
+
+        let data: Vec<u8> = (0..count)
+            .map(|offset| cpu.memory.read_byte(buffer.wrapping_add(offset)))
+            .collect();
+
+        // stdio is flushed right away, so that it interleaves like on sim65
+        let result = match descriptor {
+            1 => write_flushed(&mut io::stdout(), &data),
+            2 => write_flushed(&mut io::stderr(), &data),
+            _ => self.file(descriptor).and_then(|file| file.write(&data)),
+        };
+
+        ParaVirt::set_result(cpu, result);
+
+        HookAction::Continue
+    }
+
+    // Builds argv below the C stack and stores a pointer to it at the address
+    // in A and X. Returns argc.
+    fn args(&mut self, cpu: &mut Cpu) -> HookAction {
+        let argv = ParaVirt::get_ax(cpu);
+        let count = self.args.len() as u16;
+
+        let mut sp = cpu.memory.read_short(self.sp_address as u16);
+        let mut pointer = sp.wrapping_sub((count + 1) * 2);
+
+        cpu.memory.write_short(argv, pointer);
+        sp = pointer;
+
+        for arg in &self.args {
+            sp = sp.wrapping_sub(arg.len() as u16 + 1);
+
+            for (offset, &byte) in arg.as_bytes().iter().chain(&[0]).enumerate() {
+                cpu.memory.write_byte(sp.wrapping_add(offset as u16), byte);
+            }
+
+            cpu.memory.write_short(pointer, sp);
+            pointer = pointer.wrapping_add(2);
+        }
+
+        cpu.memory.write_short(pointer, 0);
+        cpu.memory.write_short(self.sp_address as u16, sp);
+
+        ParaVirt::set_ax(cpu, count);
+
+        HookAction::Continue
+    }
+
+    // void exit(int code), only the low byte reaches the host like on sim65
+    fn exit(&mut self, cpu: &mut Cpu) -> HookAction {
+        HookAction::Exit(cpu.registers.a as i32)
+    }
+}
+
+fn read_string(cpu: &Cpu, address: u16) -> String {
+    let bytes: Vec<u8> = (0..=u16::MAX)
+        .map(|offset| cpu.memory.read_byte(address.wrapping_add(offset)))
+        .take_while(|&byte| byte != 0)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn write_flushed(stream: &mut impl Write, data: &[u8]) -> io::Result<usize> {
+    stream.write_all(data)?;
+    stream.flush()?;
+
+    Ok(data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rs_6502_macros::asm6502;
+
+    use super::*;
+    use crate::cpu::StopReason;
+
+    #[test]
+    fn test_sim65() {
+        let program = asm6502! {
+            SP = $00
+            OPEN = $FFF4
+            CLOSE = $FFF5
+            WRITE = $FFF7
+            ARGS = $FFF8
+            EXIT = $FFF9
+            NAME = $0600
+
+                    .org $0200
+            main:   LDA #$00        // C stack below $0500
+                    STA SP
+                    LDA #$05
+                    STA SP+1
+
+                    LDA #<NAME      // open(NAME, O_WRONLY | O_CREAT | O_TRUNC)
+                    LDX #>NAME
+                    JSR pushax
+                    LDA #$32
+                    LDX #0
+                    JSR pushax
+                    LDY #4
+                    JSR OPEN
+                    STA fd
+
+                    LDX #0          // write(fd, text, 5)
+                    JSR pushax
+                    LDA #<text
+                    LDX #>text
+                    JSR pushax
+                    LDA #5
+                    LDX #0
+                    JSR WRITE
+
+                    LDA fd          // close(fd)
+                    LDX #0
+                    JSR CLOSE
+
+                    LDA #<argv      // Exits with argc
+                    LDX #>argv
+                    JSR ARGS
+                    JSR EXIT
+
+            pushax: PHA
+                    LDA SP
+                    SEC
+                    SBC #2
+                    STA SP
+                    BCS @store
+                    DEC SP+1
+            @store: LDY #1
+                    TXA
+                    STA (SP),Y
+                    DEY
+                    PLA
+                    STA (SP),Y
+                    RTS
+
+            fd:     .byte 0
+            argv:   .word 0
+            text:   .byte "hello"
+        };
+
+        let mut binary = b"sim65\x02\x00\x00".to_vec();
+        binary.extend(program.origin.to_le_bytes());
+        binary.extend(program.origin.to_le_bytes());
+        binary.extend(program.bytes);
+
+        let path = std::env::temp_dir().join(format!("rs_6502_sim65_{}", std::process::id()));
+        let args = vec![String::from("test.prg"), String::from("first")];
+
+        let mut cpu = load(&binary, args).unwrap();
+        cpu.memory.load(path.to_str().unwrap().as_bytes(), 0x0600).unwrap();
+
+        assert_eq!(
+            cpu.run_instructions(1000),
+            Ok(StopReason::Exited { code: 2 })
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello");
+        fs::remove_file(&path).unwrap();
+
+        // argv[1] points to the second argument
+        let argv = cpu.memory.read_short(program.label("argv").unwrap());
+        let first = cpu.memory.read_short(argv + 2);
+        assert_eq!(read_string(&cpu, first), "first");
+
+        assert!(Header::parse(b"sim65\x02\x05\x00\x00\x02\x00\x02").is_err());
+        assert!(Header::parse(b"sim65\x02").is_err());
+    }
+}
@@ -1,6 +1,3 @@
-use crate::registers::Flags;
-use std::mem::transmute;
-
 pub(crate) trait FromTwosComplementBits {
     type TwosComplementType;
 
@@ -11,9 +8,7 @@ impl FromTwosComplementBits for i8 {
     type TwosComplementType = u8;
 
     fn from_twos_complement_bits(val: u8) -> i8 {
-        unsafe {
-            return transmute(val);
-        }
+        val as i8
     }
 }
 
@@ -0,0 +1,80 @@
+//! Klaus Dormann's functional and decimal tests from
+//! https://github.com/Klaus2m5/6502_65C02_functional_tests. The binaries are
+//! not part of the repository, `scripts/fetch_test_roms.py` and the readme in
+//! `tests/roms` describe how to get them. Run the tests with
+//! `cargo test --release --test klaus_dormann -- --ignored`, the functional
+//! test takes about 100 million cycles.
+
+use rs_6502::cpu::{Cpu, StopReason};
+use rs_6502::default_memory::DefaultMemory;
+use rs_6502::memory::Memory;
+
+const BUDGET: u32 = 200_000_000;
+
+fn path(name: &str) -> String {
+    format!("{}/tests/roms/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+fn load(name: &str, address: u16) -> Cpu {
+    let path = path(name);
+    let mut memory = DefaultMemory::new();
+
+    if let Err(error) = memory.load_from_file(&path, address) {
+        panic!("Could not load {path}: {error}");
+    }
+
+    Cpu::with_memory(memory)
+}
+
+// The tests end in a branch or jump to itself, on success as well as on a
+// failure, or in a jam. Returns the pc the test ended at.
+fn run_until_trapped(cpu: &mut Cpu) -> u16 {
+    cpu.set_trap_detection(true);
+
+    match cpu.run_cycles(BUDGET) {
+        Ok(StopReason::Trapped { pc }) => pc,
+        Ok(StopReason::Halted(_)) => cpu.registers.pc,
+        Ok(reason) => panic!("No trap within {BUDGET} cycles, stopped with {reason:?}\n{cpu}"),
+        Err(error) => panic!("{error}\n{cpu}"),
+    }
+}
+
+#[test]
+#[ignore = "needs tests/roms/6502_functional_test.bin"]
+fn test_functional() {
+    // The image fills the address space, execution starts at $0400
+    let mut cpu = load("6502_functional_test.bin", 0x0000);
+    cpu.registers.pc = 0x0400;
+
+    let pc = run_until_trapped(&mut cpu);
+
+    // The number of the failing test is kept in `test_case`
+    assert_eq!(
+        pc,
+        0x3469,
+        "Trapped at 0x{pc:04X} in test 0x{:02X}\n{cpu}",
+        cpu.memory.read_byte(0x0200)
+    );
+}
+
+#[test]
+#[ignore = "needs tests/roms/6502_decimal_test.bin"]
+fn test_decimal() {
+    // The image starts with the variables in the zero page, TEST is at $0200
+    let mut cpu = load("6502_decimal_test.bin", 0x0000);
+    cpu.registers.pc = 0x0200;
+
+    let pc = run_until_trapped(&mut cpu);
+
+    // ERROR is cleared when all results and flags matched. N1 and N2 hold the
+    // operands of the failing operation, DA its result and AR the expected one.
+    assert_eq!(
+        cpu.memory.read_byte(0x000B),
+        0,
+        "Trapped at 0x{pc:04X} with N1 = 0x{:02X}, N2 = 0x{:02X}, DA = 0x{:02X}, AR = 0x{:02X}\n{cpu}",
+        cpu.memory.read_byte(0x0000),
+        cpu.memory.read_byte(0x0001),
+        cpu.memory.read_byte(0x0004),
+        cpu.memory.read_byte(0x0006),
+    );
+}
@@ -0,0 +1,323 @@
+//! Runs the per-opcode vectors of https://github.com/SingleStepTests/65x02.
+//! Each vector holds the state before and after a single instruction and the
+//! bus accesses in between. The files are named like `a9.json`, see the
+//! readme in `tests/single_step`. The hand-written vectors in
+//! `tests/single_step/regression` always run. The upstream vectors in
+//! `tests/single_step/subset` and the full set run with
+//! `cargo test --release --test single_step -- --ignored --nocapture`, the
+//! full set is read from the checkout of the repository that
+//! `SINGLE_STEP_TESTS` points at. Set `SINGLE_STEP_BUS` to also compare its
+//! accesses cycle by cycle.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use rs_6502::cpu::{BusCycle, BusDirection, Cpu, Variant};
+use rs_6502::default_memory::DefaultMemory;
+use rs_6502::memory::Memory;
+
+// The break and unused bits only exist on the stack, the vectors disagree
+// between chips on how they show up in the register
+const COMPARED_FLAGS: u8 = 0b1100_1111;
+
+#[derive(Deserialize)]
+struct State {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    initial: State,
+    #[serde(rename = "final")]
+    expected: State,
+    cycles: Vec<(u16, u8, String)>,
+}
+
+struct Runner {
+    cpu: Cpu,
+    compare_bus: bool,
+}
+
+impl Runner {
+    fn new(variant: Variant, compare_bus: bool) -> Runner {
+        let mut cpu = Cpu::with_variant(DefaultMemory::new(), variant);
+        cpu.set_undocumented_opcodes(true);
+
+        Runner { cpu, compare_bus }
+    }
+
+    // Returns a description of every mismatch, empty if the vector passed
+    fn run(&mut self, vector: &Vector) -> Vec<String> {
+        // Only a reset recovers from a jam in an earlier vector
+        if self.cpu.halt_reason().is_some() {
+            self.cpu.reset();
+        }
+
+        let initial = &vector.initial;
+        let registers = &mut self.cpu.registers;
+
+        registers.pc = initial.pc;
+        registers.sp = initial.s;
+        registers.a = initial.a;
+        registers.x = initial.x;
+        registers.y = initial.y;
+        registers.flags.0 = initial.p;
+
+        for &(address, value) in &initial.ram {
+            self.cpu.memory.write_byte(address, value);
+        }
+
+        let mut mismatches = Vec::new();
+
+        if let Err(error) = self.cpu.step() {
+            mismatches.push(format!("step failed: {error}"));
+        }
+
+        let registers = &self.cpu.registers;
+        let expected = &vector.expected;
+
+        let compared = [
+            ("pc", registers.pc, expected.pc),
+            ("s", registers.sp as u16, expected.s as u16),
+            ("a", registers.a as u16, expected.a as u16),
+            ("x", registers.x as u16, expected.x as u16),
+            ("y", registers.y as u16, expected.y as u16),
+            (
+                "p",
+                (registers.flags.0 & COMPARED_FLAGS) as u16,
+                (expected.p & COMPARED_FLAGS) as u16,
+            ),
+        ];
+
+        for (name, actual, expected) in compared {
+            if actual != expected {
+                mismatches.push(format!(
+                    "{name} is 0x{actual:02X}, expected 0x{expected:02X}"
+                ));
+            }
+        }
+
+        for &(address, value) in &expected.ram {
+            let actual = self.cpu.memory.read_byte(address);
+
+            if actual != value {
+                mismatches.push(format!(
+                    "[0x{address:04X}] is 0x{actual:02X}, expected 0x{value:02X}"
+                ));
+            }
+        }
+
+        let accesses = self.cpu.bus_cycles();
+
+        if accesses.len() != vector.cycles.len() {
+            mismatches.push(format!(
+                "took {} cycles, expected {}",
+                accesses.len(),
+                vector.cycles.len()
+            ));
+        } else if self.compare_bus {
+            for (cycle, (access, expected)) in accesses.iter().zip(&vector.cycles).enumerate() {
+                if !matches(access, expected) {
+                    mismatches.push(format!(
+                        "cycle {cycle} was {access:?}, expected {expected:?}"
+                    ));
+                }
+            }
+        }
+
+        self.clear(vector);
+
+        mismatches
+    }
+
+    // Vectors list every address they touch, the rest of memory stays zero
+    fn clear(&mut self, vector: &Vector) {
+        let addresses = vector.initial.ram.iter().chain(&vector.expected.ram);
+        let accesses = vector.cycles.iter().map(|&(address, ..)| address);
+
+        for address in addresses.map(|&(address, _)| address).chain(accesses) {
+            self.cpu.memory.write_byte(address, 0);
+        }
+    }
+}
+
+fn matches(access: &BusCycle, (address, value, direction): &(u16, u8, String)) -> bool {
+    let direction = match direction.as_str() {
+        "read" => BusDirection::Read,
+        _ => BusDirection::Write,
+    };
+
+    access.address == *address && access.value == *value && access.direction == direction
+}
+
+struct Summary {
+    passed: usize,
+    failed: usize,
+    first_failure: Option<String>,
+}
+
+fn run_file(runner: &mut Runner, path: &Path) -> Summary {
+    let json = fs::read_to_string(path).unwrap_or_else(|error| panic!("{path:?}: {error}"));
+    let vectors: Vec<Vector> =
+        serde_json::from_str(&json).unwrap_or_else(|error| panic!("{path:?}: {error}"));
+
+    let mut summary = Summary {
+        passed: 0,
+        failed: 0,
+        first_failure: None,
+    };
+
+    for vector in &vectors {
+        let mismatches = runner.run(vector);
+
+        if mismatches.is_empty() {
+            summary.passed += 1;
+            continue;
+        }
+
+        summary.failed += 1;
+        summary
+            .first_failure
+            .get_or_insert_with(|| format!("{}: {}", vector.name, mismatches.join(", ")));
+    }
+
+    summary
+}
+
+// Runs the vectors of all 256 opcodes and prints a line per opcode. Fails if
+// any vector failed or if there were no vectors at all.
+fn run_chip(directory: &Path, variant: Variant, compare_bus: bool) {
+    let mut runner = Runner::new(variant, compare_bus);
+    let mut report = String::new();
+    let (mut failed, mut missing) = (Vec::new(), Vec::new());
+
+    for opcode in 0..=0xFF_u8 {
+        let path = directory.join(format!("{opcode:02x}.json"));
+
+        if !path.exists() {
+            missing.push(opcode);
+            continue;
+        }
+
+        let summary = run_file(&mut runner, &path);
+        let total = summary.passed + summary.failed;
+
+        match summary.first_failure {
+            Some(failure) => {
+                failed.push(opcode);
+                writeln!(
+                    report,
+                    "{opcode:02X}: {}/{total} FAILED, {failure}",
+                    summary.passed
+                )
+            }
+            None => writeln!(report, "{opcode:02X}: {total}/{total} passed"),
+        }
+        .unwrap();
+    }
+
+    println!("{report}");
+    println!("{} failed, {} missing", failed.len(), missing.len());
+
+    assert!(missing.len() < 256, "No vectors in {directory:?}");
+    assert!(failed.is_empty(), "Failing opcodes: {failed:02X?}");
+}
+
+// `kind` is `regression` or `subset`
+fn vectors(kind: &str, chip: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/single_step")
+        .join(kind)
+        .join(chip)
+}
+
+// The directory of the chip in the checkout named by `SINGLE_STEP_TESTS`
+fn full_set(chip: &str) -> PathBuf {
+    let checkout = env::var_os("SINGLE_STEP_TESTS")
+        .expect("SINGLE_STEP_TESTS names a checkout of the full set of vectors");
+
+    Path::new(&checkout).join(chip).join("v1")
+}
+
+#[test]
+fn test_nmos6502_regression() {
+    run_chip(&vectors("regression", "6502"), Variant::Nmos6502, true);
+}
+
+#[test]
+fn test_cmos65c02_regression() {
+    run_chip(&vectors("regression", "65c02"), Variant::Cmos65C02, true);
+}
+
+#[test]
+#[ignore = "needs vectors from scripts/vendor_single_step_tests.py"]
+fn test_nmos6502_subset() {
+    run_chip(&vectors("subset", "6502"), Variant::Nmos6502, true);
+}
+
+#[test]
+#[ignore = "needs vectors from scripts/vendor_single_step_tests.py"]
+fn test_cmos65c02_subset() {
+    run_chip(&vectors("subset", "65c02"), Variant::Cmos65C02, true);
+}
+
+#[test]
+#[ignore = "needs SINGLE_STEP_TESTS"]
+fn test_nmos6502() {
+    let compare_bus = env::var_os("SINGLE_STEP_BUS").is_some();
+    run_chip(&full_set("6502"), Variant::Nmos6502, compare_bus);
+}
+
+#[test]
+#[ignore = "needs SINGLE_STEP_TESTS"]
+fn test_cmos65c02() {
+    let compare_bus = env::var_os("SINGLE_STEP_BUS").is_some();
+    run_chip(&full_set("wdc65c02"), Variant::Cmos65C02, compare_bus);
+}
+
+#[test]
+fn test_runner() {
+    // LDA #$80 followed by a vector that expects the wrong result
+    let json = r#"[
+        {
+            "name": "a9 80 00",
+            "initial": { "pc": 4096, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                         "ram": [[4096, 169], [4097, 128]] },
+            "final": { "pc": 4098, "s": 253, "a": 128, "x": 0, "y": 0, "p": 164,
+                       "ram": [[4096, 169], [4097, 128]] },
+            "cycles": [[4097, 128, "read"], [4098, 0, "read"]]
+        },
+        {
+            "name": "a9 80 01",
+            "initial": { "pc": 4096, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                         "ram": [[4096, 169], [4097, 128]] },
+            "final": { "pc": 4098, "s": 253, "a": 127, "x": 0, "y": 0, "p": 164,
+                       "ram": [[4096, 169], [4097, 128]] },
+            "cycles": [[4096, 169, "read"], [4097, 128, "read"]]
+        }
+    ]"#;
+
+    let vectors: Vec<Vector> = serde_json::from_str(json).unwrap();
+    let mut runner = Runner::new(Variant::Nmos6502, true);
+
+    assert_eq!(
+        runner.run(&vectors[0]),
+        [
+            "cycle 0 was BusCycle { address: 4096, value: 169, direction: Read }, expected (4097, 128, \"read\")",
+            "cycle 1 was BusCycle { address: 4097, value: 128, direction: Read }, expected (4098, 0, \"read\")",
+        ]
+    );
+    assert_eq!(runner.run(&vectors[1]), ["a is 0x80, expected 0x7F"]);
+    assert_eq!(runner.cpu.memory.read_byte(4096), 0);
+}